@@ -0,0 +1,88 @@
+use anyhow::Result;
+
+use crate::geometry::Rect;
+
+pub mod fake;
+#[cfg(windows)]
+pub mod win32;
+
+/// A display attached to the desktop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Monitor {
+    pub rect: Rect,
+    /// The part of `rect` not reserved by the taskbar or docked app bars
+    pub work_area: Rect,
+    pub primary: bool,
+}
+
+/// Extended styles that change how a window is treated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Styles {
+    /// `WS_EX_TOOLWINDOW`, floating palettes and toolbars kept off the taskbar
+    pub tool_window: bool,
+    /// `WS_EX_APPWINDOW`, put on the taskbar even when owned or a tool window
+    pub app_window: bool,
+    /// Layered and either click-through or fully transparent, like overlays
+    pub transparent: bool,
+}
+
+/// Opaque handle of a top-level window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowId(pub isize);
+
+/// Everything the rescue logic needs from the window manager
+pub trait WindowSystem {
+    /// Enumerate top-level windows in z-order
+    fn windows(&self) -> Result<Vec<WindowId>>;
+
+    /// Every attached monitor, in virtual screen coordinates
+    fn monitors(&self) -> Result<Vec<Monitor>>;
+
+    fn cursor_pos(&self) -> Result<(i32, i32)>;
+
+    fn is_visible(&self, window: WindowId) -> bool;
+
+    fn is_minimized(&self, window: WindowId) -> bool;
+
+    fn is_maximized(&self, window: WindowId) -> bool;
+
+    /// Whether the owning thread has stopped pumping messages
+    fn is_hung(&self, window: WindowId) -> bool;
+
+    fn styles(&self, window: WindowId) -> Styles;
+
+    /// Window that owns this one, like the main window of a dialog
+    fn owner(&self, window: WindowId) -> Option<WindowId>;
+
+    /// Whether DWM hides the window, like UWP frames on other virtual desktops
+    fn is_cloaked(&self, window: WindowId) -> bool;
+
+    /// Errors carry [`AccessDenied`](crate::error::AccessDenied) when the OS refused the call
+    fn window_text(&self, window: WindowId) -> Result<String>;
+
+    /// Name of the window class the window was created with
+    fn class_name(&self, window: WindowId) -> Result<String>;
+
+    /// File name of the executable of the owning process, like `mstsc.exe`
+    fn process_name(&self, window: WindowId) -> Result<String>;
+
+    fn window_rect(&self, window: WindowId) -> Result<Rect>;
+
+    /// Rect the window occupies when neither minimized nor maximized
+    fn normal_rect(&self, window: WindowId) -> Result<Rect>;
+
+    /// Change the rect a window returns to when restored, without changing its state
+    fn set_normal_rect(&mut self, window: WindowId, rect: Rect) -> Result<()>;
+
+    /// Restore a maximized or minimized window to its normal state
+    fn restore(&mut self, window: WindowId) -> Result<()>;
+
+    /// Maximize a window on the monitor it is on
+    fn maximize(&mut self, window: WindowId) -> Result<()>;
+
+    /// Move a window without resizing it
+    fn move_to(&mut self, window: WindowId, x: i32, y: i32) -> Result<()>;
+
+    /// Resize a window without moving it
+    fn resize(&mut self, window: WindowId, width: i32, height: i32) -> Result<()>;
+}
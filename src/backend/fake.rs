@@ -0,0 +1,384 @@
+use anyhow::{anyhow, bail, Result};
+
+use super::{Monitor, Styles, WindowId, WindowSystem};
+use crate::error::AccessDenied;
+use crate::geometry::Rect;
+use crate::placement::work_area_at;
+
+/// A backend call that can be made to fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    Text,
+    Rect,
+    Placement,
+    SetPlacement,
+    Restore,
+    Maximize,
+    Move,
+    Resize,
+}
+
+/// Monitor edge a taskbar or app bar can be docked to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+/// A state-changing call recorded by the fake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Call {
+    SetNormalRect(WindowId, Rect),
+    Restore(WindowId),
+    Maximize(WindowId),
+    Move(WindowId, i32, i32),
+    Resize(WindowId, i32, i32),
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeWindow {
+    pub title: String,
+    pub class: String,
+    pub process: String,
+    pub rect: Rect,
+    /// Rect the window returns to when restored
+    pub normal_rect: Rect,
+    pub styles: Styles,
+    pub owner: Option<WindowId>,
+    pub cloaked: bool,
+    pub visible: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub hung: bool,
+    /// State-changing calls are refused as if the window were elevated
+    pub elevated: bool,
+    pub faults: Vec<Fault>,
+}
+
+impl FakeWindow {
+    pub fn new(title: &str, rect: Rect) -> Self {
+        Self {
+            title: title.to_string(),
+            class: String::new(),
+            process: String::new(),
+            rect,
+            normal_rect: rect,
+            styles: Styles::default(),
+            owner: None,
+            cloaked: false,
+            visible: true,
+            minimized: false,
+            maximized: false,
+            hung: false,
+            elevated: false,
+            faults: Vec::new(),
+        }
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+
+    pub fn process(mut self, process: &str) -> Self {
+        self.process = process.to_string();
+        self
+    }
+
+    pub fn tool_window(mut self) -> Self {
+        self.styles.tool_window = true;
+        self
+    }
+
+    pub fn app_window(mut self) -> Self {
+        self.styles.app_window = true;
+        self
+    }
+
+    pub fn transparent(mut self) -> Self {
+        self.styles.transparent = true;
+        self
+    }
+
+    pub fn owned_by(mut self, owner: WindowId) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    pub fn cloaked(mut self) -> Self {
+        self.cloaked = true;
+        self
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.visible = false;
+        self
+    }
+
+    /// Iconic windows are parked far off-screen, the rect they were created with is kept
+    /// as the one they restore to
+    pub fn minimized(mut self) -> Self {
+        self.minimized = true;
+        self.rect = Rect::new(-32000, -32000, -31840, -31972);
+        self
+    }
+
+    pub fn maximized(mut self, normal_rect: Rect) -> Self {
+        self.maximized = true;
+        self.normal_rect = normal_rect;
+        self
+    }
+
+    pub fn hung(mut self) -> Self {
+        self.hung = true;
+        self
+    }
+
+    pub fn elevated(mut self) -> Self {
+        self.elevated = true;
+        self
+    }
+
+    pub fn failing(mut self, fault: Fault) -> Self {
+        self.faults.push(fault);
+        self
+    }
+
+    fn check(&self, fault: Fault) -> Result<()> {
+        if self.faults.contains(&fault) {
+            bail!("Injected {fault:?} failure for {:?}", self.title);
+        }
+        if self.elevated
+            && matches!(
+                fault,
+                Fault::SetPlacement
+                    | Fault::Restore
+                    | Fault::Maximize
+                    | Fault::Move
+                    | Fault::Resize
+            )
+        {
+            return Err(AccessDenied.into());
+        }
+        Ok(())
+    }
+}
+
+/// Scriptable in-memory desktop
+#[derive(Debug, Clone)]
+pub struct FakeWindowSystem {
+    monitors: Vec<Monitor>,
+    cursor: (i32, i32),
+    windows: Vec<(WindowId, FakeWindow)>,
+    calls: Vec<Call>,
+}
+
+impl FakeWindowSystem {
+    /// A desktop with a single primary monitor at the origin
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            monitors: vec![Monitor {
+                rect: Rect::new(0, 0, width, height),
+                work_area: Rect::new(0, 0, width, height),
+                primary: true,
+            }],
+            cursor: (width / 2, height / 2),
+            windows: Vec::new(),
+            calls: Vec::new(),
+        }
+    }
+
+    /// Attach a secondary monitor and return its index
+    pub fn add_monitor(&mut self, rect: Rect) -> usize {
+        self.monitors.push(Monitor {
+            rect,
+            work_area: rect,
+            primary: false,
+        });
+        self.monitors.len() - 1
+    }
+
+    pub fn set_cursor(&mut self, x: i32, y: i32) {
+        self.cursor = (x, y);
+    }
+
+    /// Dock a bar of `thickness` pixels to an edge of a monitor, shrinking its work area
+    pub fn reserve(&mut self, monitor: usize, edge: Edge, thickness: i32) {
+        let work_area = &mut self.monitors[monitor].work_area;
+        match edge {
+            Edge::Left => work_area.left += thickness,
+            Edge::Top => work_area.top += thickness,
+            Edge::Right => work_area.right -= thickness,
+            Edge::Bottom => work_area.bottom -= thickness,
+        }
+    }
+
+    /// Add a window on top of the z-order list and return its handle
+    pub fn add(&mut self, window: FakeWindow) -> WindowId {
+        let id = WindowId(self.windows.len() as isize + 1);
+        self.windows.push((id, window));
+        id
+    }
+
+    pub fn window(&self, id: WindowId) -> &FakeWindow {
+        self.get(id).expect("unknown fake window")
+    }
+
+    /// Change a window behind the back of the code under test, like the user would
+    pub fn window_mut(&mut self, id: WindowId) -> &mut FakeWindow {
+        self.get_mut(id).expect("unknown fake window")
+    }
+
+    pub fn calls(&self) -> &[Call] {
+        &self.calls
+    }
+
+    fn get(&self, id: WindowId) -> Result<&FakeWindow> {
+        self.windows
+            .iter()
+            .find(|(window_id, _)| *window_id == id)
+            .map(|(_, window)| window)
+            .ok_or_else(|| anyhow!("Invalid window handle {id:?}"))
+    }
+
+    fn get_mut(&mut self, id: WindowId) -> Result<&mut FakeWindow> {
+        self.windows
+            .iter_mut()
+            .find(|(window_id, _)| *window_id == id)
+            .map(|(_, window)| window)
+            .ok_or_else(|| anyhow!("Invalid window handle {id:?}"))
+    }
+}
+
+impl WindowSystem for FakeWindowSystem {
+    fn windows(&self) -> Result<Vec<WindowId>> {
+        Ok(self.windows.iter().map(|(id, _)| *id).collect())
+    }
+
+    fn monitors(&self) -> Result<Vec<Monitor>> {
+        Ok(self.monitors.clone())
+    }
+
+    fn cursor_pos(&self) -> Result<(i32, i32)> {
+        Ok(self.cursor)
+    }
+
+    fn is_visible(&self, window: WindowId) -> bool {
+        self.get(window).is_ok_and(|window| window.visible)
+    }
+
+    fn is_minimized(&self, window: WindowId) -> bool {
+        self.get(window).is_ok_and(|window| window.minimized)
+    }
+
+    fn is_maximized(&self, window: WindowId) -> bool {
+        self.get(window).is_ok_and(|window| window.maximized)
+    }
+
+    fn is_hung(&self, window: WindowId) -> bool {
+        self.get(window).is_ok_and(|window| window.hung)
+    }
+
+    fn styles(&self, window: WindowId) -> Styles {
+        self.get(window)
+            .map(|window| window.styles)
+            .unwrap_or_default()
+    }
+
+    fn owner(&self, window: WindowId) -> Option<WindowId> {
+        self.get(window).ok().and_then(|window| window.owner)
+    }
+
+    fn is_cloaked(&self, window: WindowId) -> bool {
+        self.get(window).is_ok_and(|window| window.cloaked)
+    }
+
+    fn window_text(&self, window: WindowId) -> Result<String> {
+        let window = self.get(window)?;
+        window.check(Fault::Text)?;
+        Ok(window.title.clone())
+    }
+
+    fn class_name(&self, window: WindowId) -> Result<String> {
+        Ok(self.get(window)?.class.clone())
+    }
+
+    fn process_name(&self, window: WindowId) -> Result<String> {
+        let window = self.get(window)?;
+        if window.elevated {
+            return Err(AccessDenied.into());
+        }
+        Ok(window.process.clone())
+    }
+
+    fn window_rect(&self, window: WindowId) -> Result<Rect> {
+        let window = self.get(window)?;
+        window.check(Fault::Rect)?;
+        Ok(window.rect)
+    }
+
+    fn normal_rect(&self, window: WindowId) -> Result<Rect> {
+        let window = self.get(window)?;
+        window.check(Fault::Placement)?;
+        Ok(window.normal_rect)
+    }
+
+    fn set_normal_rect(&mut self, id: WindowId, rect: Rect) -> Result<()> {
+        let window = self.get_mut(id)?;
+        window.check(Fault::SetPlacement)?;
+        window.normal_rect = rect;
+        if !window.minimized && !window.maximized {
+            window.rect = rect;
+        }
+        self.calls.push(Call::SetNormalRect(id, rect));
+        Ok(())
+    }
+
+    fn restore(&mut self, id: WindowId) -> Result<()> {
+        let window = self.get_mut(id)?;
+        window.check(Fault::Restore)?;
+        window.maximized = false;
+        window.minimized = false;
+        window.rect = window.normal_rect;
+        self.calls.push(Call::Restore(id));
+        Ok(())
+    }
+
+    /// Fills the work area of the monitor the normal rect starts on
+    fn maximize(&mut self, id: WindowId) -> Result<()> {
+        let window = self.get(id)?;
+        window.check(Fault::Maximize)?;
+        let area = work_area_at(
+            &self.monitors,
+            window.normal_rect.left,
+            window.normal_rect.top,
+        );
+        let window = self.get_mut(id)?;
+        window.maximized = true;
+        window.minimized = false;
+        window.rect = area;
+        self.calls.push(Call::Maximize(id));
+        Ok(())
+    }
+
+    fn move_to(&mut self, id: WindowId, x: i32, y: i32) -> Result<()> {
+        let window = self.get_mut(id)?;
+        window.check(Fault::Move)?;
+        window.rect = window.rect.moved_to(x, y);
+        window.normal_rect = window.rect;
+        self.calls.push(Call::Move(id, x, y));
+        Ok(())
+    }
+
+    fn resize(&mut self, id: WindowId, width: i32, height: i32) -> Result<()> {
+        let window = self.get_mut(id)?;
+        window.check(Fault::Resize)?;
+        let rect = window.rect;
+        window.rect = Rect::new(rect.left, rect.top, rect.left + width, rect.top + height);
+        window.normal_rect = window.rect;
+        self.calls.push(Call::Resize(id, width, height));
+        Ok(())
+    }
+}
@@ -0,0 +1,313 @@
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, BOOL, E_ACCESSDENIED, HWND, LPARAM, POINT, RECT};
+use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED};
+use windows::Win32::Graphics::Gdi::{
+    EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO,
+};
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetClassNameW, GetCursorPos, GetLayeredWindowAttributes, GetWindow,
+    GetWindowLongW, GetWindowPlacement, GetWindowRect, GetWindowTextLengthW, GetWindowTextW,
+    GetWindowThreadProcessId, IsHungAppWindow, IsIconic, IsWindowVisible, IsZoomed,
+    SetWindowPlacement, SetWindowPos, ShowWindow, SystemParametersInfoW, GWL_EXSTYLE, GW_OWNER,
+    LAYERED_WINDOW_ATTRIBUTES_FLAGS, LWA_ALPHA, MONITORINFOF_PRIMARY, SPI_GETWORKAREA,
+    SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SWP_NOZORDER, SW_MAXIMIZE, SW_RESTORE,
+    SW_SHOWMINNOACTIVE, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, WINDOWPLACEMENT, WS_EX_APPWINDOW,
+    WS_EX_LAYERED, WS_EX_TOOLWINDOW, WS_EX_TRANSPARENT,
+};
+
+use super::{Monitor, Styles, WindowId, WindowSystem};
+use crate::error::AccessDenied;
+use crate::geometry::Rect;
+use crate::wide_string_to_string;
+
+/// The live Win32 desktop
+pub struct Win32;
+
+impl From<RECT> for Rect {
+    fn from(rect: RECT) -> Self {
+        Rect::new(rect.left, rect.top, rect.right, rect.bottom)
+    }
+}
+
+fn hwnd(window: WindowId) -> HWND {
+    HWND(window.0 as _)
+}
+
+/// Surface access denied as the backend-neutral marker
+fn check<T>(result: windows::core::Result<T>) -> Result<T> {
+    result.map_err(|e| {
+        if e.code() == E_ACCESSDENIED {
+            AccessDenied.into()
+        } else {
+            e.into()
+        }
+    })
+}
+
+fn ex_style(hwnd: HWND) -> u32 {
+    unsafe { GetWindowLongW(hwnd, GWL_EXSTYLE) as u32 }
+}
+
+fn placement(hwnd: HWND) -> Result<WINDOWPLACEMENT> {
+    let mut placement = WINDOWPLACEMENT {
+        length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+        ..Default::default()
+    };
+    check(unsafe { GetWindowPlacement(hwnd, &mut placement) })?;
+    Ok(placement)
+}
+
+/// Origin of the coordinates of a window placement in screen coordinates
+///
+/// Tool windows use screen coordinates, everything else is relative to the work area.
+fn placement_origin(hwnd: HWND) -> Result<(i32, i32)> {
+    if ex_style(hwnd) & WS_EX_TOOLWINDOW.0 != 0 {
+        return Ok((0, 0));
+    }
+    let mut work_area = RECT::default();
+    unsafe {
+        SystemParametersInfoW(
+            SPI_GETWORKAREA,
+            0,
+            Some(&mut work_area as *mut RECT as _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )?
+    };
+    Ok((work_area.left, work_area.top))
+}
+
+unsafe extern "system" fn enum_window_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let windows = &mut *(lparam.0 as *mut Vec<WindowId>);
+    windows.push(WindowId(hwnd.0 as isize));
+    BOOL(1)
+}
+
+unsafe extern "system" fn enum_monitor_callback(
+    hmonitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let monitors = &mut *(lparam.0 as *mut Vec<Monitor>);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    if GetMonitorInfoW(hmonitor, &mut info).as_bool() {
+        monitors.push(Monitor {
+            rect: info.rcMonitor.into(),
+            work_area: info.rcWork.into(),
+            primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+        });
+    }
+    BOOL(1)
+}
+
+impl WindowSystem for Win32 {
+    fn windows(&self) -> Result<Vec<WindowId>> {
+        let mut windows = Vec::new();
+        unsafe {
+            EnumWindows(
+                Some(enum_window_callback),
+                LPARAM(&mut windows as *mut Vec<WindowId> as isize),
+            )?
+        };
+        Ok(windows)
+    }
+
+    fn monitors(&self) -> Result<Vec<Monitor>> {
+        let mut monitors = Vec::new();
+        unsafe {
+            EnumDisplayMonitors(
+                None,
+                None,
+                Some(enum_monitor_callback),
+                LPARAM(&mut monitors as *mut Vec<Monitor> as isize),
+            )
+            .ok()?
+        };
+        Ok(monitors)
+    }
+
+    fn cursor_pos(&self) -> Result<(i32, i32)> {
+        let mut point = POINT::default();
+        unsafe { GetCursorPos(&mut point)? };
+        Ok((point.x, point.y))
+    }
+
+    fn is_visible(&self, window: WindowId) -> bool {
+        unsafe { IsWindowVisible(hwnd(window)).as_bool() }
+    }
+
+    fn is_minimized(&self, window: WindowId) -> bool {
+        unsafe { IsIconic(hwnd(window)).as_bool() }
+    }
+
+    fn is_maximized(&self, window: WindowId) -> bool {
+        unsafe { IsZoomed(hwnd(window)).as_bool() }
+    }
+
+    fn is_hung(&self, window: WindowId) -> bool {
+        unsafe { IsHungAppWindow(hwnd(window)).as_bool() }
+    }
+
+    fn styles(&self, window: WindowId) -> Styles {
+        let hwnd = hwnd(window);
+        let ex_style = ex_style(hwnd);
+        let has = |style: u32| ex_style & style != 0;
+        let transparent = has(WS_EX_LAYERED.0)
+            && (has(WS_EX_TRANSPARENT.0) || {
+                let mut alpha = 255u8;
+                let mut flags = LAYERED_WINDOW_ATTRIBUTES_FLAGS(0);
+                // Layered windows drawn with UpdateLayeredWindow have no attributes to read
+                let read = unsafe {
+                    GetLayeredWindowAttributes(hwnd, None, Some(&mut alpha), Some(&mut flags))
+                };
+                read.is_ok() && flags.contains(LWA_ALPHA) && alpha == 0
+            });
+        Styles {
+            tool_window: has(WS_EX_TOOLWINDOW.0),
+            app_window: has(WS_EX_APPWINDOW.0),
+            transparent,
+        }
+    }
+
+    fn owner(&self, window: WindowId) -> Option<WindowId> {
+        let owner = unsafe { GetWindow(hwnd(window), GW_OWNER) }.ok()?;
+        Some(WindowId(owner.0 as isize))
+    }
+
+    fn is_cloaked(&self, window: WindowId) -> bool {
+        let mut cloaked = 0u32;
+        let result = unsafe {
+            DwmGetWindowAttribute(
+                hwnd(window),
+                DWMWA_CLOAKED,
+                &mut cloaked as *mut u32 as _,
+                std::mem::size_of::<u32>() as u32,
+            )
+        };
+        result.is_ok() && cloaked != 0
+    }
+
+    fn window_text(&self, window: WindowId) -> Result<String> {
+        let hwnd = hwnd(window);
+        let text_length = unsafe { GetWindowTextLengthW(hwnd) };
+        let mut wide_buffer = vec![0u16; (text_length + 1) as usize];
+        unsafe { GetWindowTextW(hwnd, &mut wide_buffer) };
+        wide_string_to_string(&wide_buffer)
+            .with_context(|| format!("Failed to convert wide string to string: {:?}", wide_buffer))
+    }
+
+    fn class_name(&self, window: WindowId) -> Result<String> {
+        // Class names are at most 256 characters
+        let mut buffer = [0u16; 257];
+        let length = unsafe { GetClassNameW(hwnd(window), &mut buffer) };
+        if length == 0 {
+            check(Err(windows::core::Error::from_win32()))?;
+        }
+        wide_string_to_string(&buffer[..length as usize])
+    }
+
+    fn process_name(&self, window: WindowId) -> Result<String> {
+        let mut process_id = 0;
+        unsafe { GetWindowThreadProcessId(hwnd(window), Some(&mut process_id)) };
+        let process =
+            check(unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id) })?;
+        let mut buffer = vec![0u16; 1024];
+        let mut length = buffer.len() as u32;
+        let result = unsafe {
+            QueryFullProcessImageNameW(
+                process,
+                PROCESS_NAME_WIN32,
+                PWSTR(buffer.as_mut_ptr()),
+                &mut length,
+            )
+        };
+        unsafe { CloseHandle(process) }?;
+        check(result)?;
+        let path = wide_string_to_string(&buffer[..length as usize])?;
+        Ok(path.rsplit('\\').next().unwrap_or_default().to_string())
+    }
+
+    fn window_rect(&self, window: WindowId) -> Result<Rect> {
+        let mut rect = RECT::default();
+        check(unsafe { GetWindowRect(hwnd(window), &mut rect) })?;
+        Ok(rect.into())
+    }
+
+    fn normal_rect(&self, window: WindowId) -> Result<Rect> {
+        let hwnd = hwnd(window);
+        let rect = Rect::from(placement(hwnd)?.rcNormalPosition);
+        let (x, y) = placement_origin(hwnd)?;
+        Ok(rect.offset(x, y))
+    }
+
+    fn set_normal_rect(&mut self, window: WindowId, rect: Rect) -> Result<()> {
+        let hwnd = hwnd(window);
+        // Keeping showCmd leaves a minimized window minimized, but SW_SHOWMINIMIZED would
+        // activate it
+        let mut placement = placement(hwnd)?;
+        if unsafe { IsIconic(hwnd) }.as_bool() {
+            placement.showCmd = SW_SHOWMINNOACTIVE.0 as u32;
+        }
+        let (x, y) = placement_origin(hwnd)?;
+        let rect = rect.offset(-x, -y);
+        placement.rcNormalPosition = RECT {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        };
+        check(unsafe { SetWindowPlacement(hwnd, &placement) })
+    }
+
+    fn restore(&mut self, window: WindowId) -> Result<()> {
+        check(unsafe { ShowWindow(hwnd(window), SW_RESTORE).ok() })
+    }
+
+    fn maximize(&mut self, window: WindowId) -> Result<()> {
+        // The BOOL returned is whether the window was visible before, not success
+        unsafe {
+            let _ = ShowWindow(hwnd(window), SW_MAXIMIZE);
+            if !IsZoomed(hwnd(window)).as_bool() {
+                bail!("Window did not maximize");
+            }
+        }
+        Ok(())
+    }
+
+    fn move_to(&mut self, window: WindowId, x: i32, y: i32) -> Result<()> {
+        check(unsafe {
+            SetWindowPos(
+                hwnd(window),
+                None,
+                x,
+                y,
+                0,
+                0,
+                SWP_NOZORDER | SWP_NOSIZE | SWP_NOACTIVATE,
+            )
+        })
+    }
+
+    fn resize(&mut self, window: WindowId, width: i32, height: i32) -> Result<()> {
+        check(unsafe {
+            SetWindowPos(
+                hwnd(window),
+                None,
+                0,
+                0,
+                width,
+                height,
+                SWP_NOZORDER | SWP_NOMOVE | SWP_NOACTIVATE,
+            )
+        })
+    }
+}
@@ -0,0 +1,273 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::geometry::Size;
+use crate::layout::Layout;
+use crate::placement::PlacementStrategy;
+use crate::plan::PlanOptions;
+use crate::rescue::{Detection, ThresholdOverride, Thresholds};
+use crate::rules::Rule;
+use crate::state;
+use crate::window::Scope;
+
+/// Settings read from `config.toml`, command line options take precedence
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub strategy: Option<PlacementStrategy>,
+    pub layout: Option<Layout>,
+    pub cascade_step: Option<i32>,
+    pub fit: Option<bool>,
+    pub min_size: Option<Size>,
+    pub keep_aspect: Option<bool>,
+    pub detection: Option<Detection>,
+    pub min_visible: Option<f32>,
+    pub top_left_bound: Option<i32>,
+    pub title_bar_pixels: Option<i32>,
+    /// `[[override]]` tables
+    #[serde(rename = "override")]
+    pub overrides: Vec<ThresholdOverride>,
+    pub tool_windows: Option<bool>,
+    pub owned_windows: Option<bool>,
+    pub cloaked_windows: Option<bool>,
+    pub transparent_windows: Option<bool>,
+    /// `[[rule]]` tables, after the rules given on the command line
+    #[serde(rename = "rule")]
+    pub rules: Vec<Rule>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        state::read(path)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// `%APPDATA%\moswb\config.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        state::app_path("config.toml")
+    }
+
+    /// The file at `path`, or the one at the default path if it exists
+    pub fn find(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None => match Self::default_path().filter(|path| path.exists()) {
+                Some(path) => Self::load(&path),
+                None => Ok(Self::default()),
+            },
+        }
+    }
+
+    /// Settings of `self` where set and of `under` elsewhere, the rules of `self` first
+    ///
+    /// Command line options go over the config file this way.
+    pub fn over(self, under: Config) -> Config {
+        Config {
+            strategy: self.strategy.or(under.strategy),
+            layout: self.layout.or(under.layout),
+            cascade_step: self.cascade_step.or(under.cascade_step),
+            fit: self.fit.or(under.fit),
+            min_size: self.min_size.or(under.min_size),
+            keep_aspect: self.keep_aspect.or(under.keep_aspect),
+            detection: self.detection.or(under.detection),
+            min_visible: self.min_visible.or(under.min_visible),
+            top_left_bound: self.top_left_bound.or(under.top_left_bound),
+            title_bar_pixels: self.title_bar_pixels.or(under.title_bar_pixels),
+            overrides: self.overrides.into_iter().chain(under.overrides).collect(),
+            tool_windows: self.tool_windows.or(under.tool_windows),
+            owned_windows: self.owned_windows.or(under.owned_windows),
+            cloaked_windows: self.cloaked_windows.or(under.cloaked_windows),
+            transparent_windows: self.transparent_windows.or(under.transparent_windows),
+            rules: self.rules.into_iter().chain(under.rules).collect(),
+        }
+    }
+
+    /// Planner options with defaults for what is not set, rejecting invalid values
+    pub fn plan_options(self) -> Result<PlanOptions> {
+        let defaults = PlanOptions::default();
+        let cascade_step = self.cascade_step.unwrap_or(defaults.cascade_step);
+        if cascade_step <= 0 {
+            bail!("Cascade step must be positive, got {cascade_step}");
+        }
+
+        let thresholds = Thresholds {
+            min_visible: self.min_visible.unwrap_or(defaults.thresholds.min_visible),
+            top_left_bound: self
+                .top_left_bound
+                .unwrap_or(defaults.thresholds.top_left_bound),
+            title_bar_pixels: self.title_bar_pixels,
+        };
+        thresholds.validate()?;
+        for threshold_override in &self.overrides {
+            threshold_override
+                .validate()
+                .with_context(|| format!("Invalid override {threshold_override}"))?;
+        }
+
+        Ok(PlanOptions {
+            strategy: self.strategy.unwrap_or_default(),
+            layout: self.layout.unwrap_or_default(),
+            cascade_step,
+            fit: self.fit.unwrap_or(defaults.fit),
+            min_size: self.min_size.unwrap_or(defaults.min_size),
+            keep_aspect: self.keep_aspect.unwrap_or(defaults.keep_aspect),
+            detection: self.detection.unwrap_or_default(),
+            thresholds,
+            overrides: self.overrides,
+            rules: self.rules,
+            scope: Scope {
+                tool_windows: self.tool_windows.unwrap_or(false),
+                owned_windows: self.owned_windows.unwrap_or(false),
+                cloaked_windows: self.cloaked_windows.unwrap_or(false),
+                transparent_windows: self.transparent_windows.unwrap_or(false),
+            },
+            ..defaults
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleAction;
+
+    #[test]
+    fn empty_config() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn strategy() {
+        let config = Config::parse(r#"strategy = "relative:1""#).unwrap();
+        assert_eq!(config.strategy, Some(PlacementStrategy::Relative(1)));
+    }
+
+    #[test]
+    fn cascade() {
+        let config = Config::parse("layout = \"cascade\"\ncascade-step = 48").unwrap();
+        assert_eq!(config.layout, Some(Layout::Cascade));
+        assert_eq!(config.cascade_step, Some(48));
+    }
+
+    #[test]
+    fn fit() {
+        let config =
+            Config::parse("fit = true\nmin-size = \"800x600\"\nkeep-aspect = true").unwrap();
+        assert_eq!(config.fit, Some(true));
+        assert_eq!(config.min_size, Some(Size::new(800, 600)));
+        assert_eq!(config.keep_aspect, Some(true));
+        assert!(Config::parse(r#"min-size = "800""#).is_err());
+    }
+
+    #[test]
+    fn thresholds_and_overrides() {
+        let config = Config::parse(
+            r#"
+            min-visible = 0.7
+            top-left-bound = 50
+
+            [[override]]
+            monitor = 1
+            title-bar-pixels = 120
+
+            [[override]]
+            title = "Remote Desktop"
+            min-visible = 0.2
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.min_visible, Some(0.7));
+        assert_eq!(config.top_left_bound, Some(50));
+        assert_eq!(
+            config.overrides,
+            [
+                ThresholdOverride {
+                    monitor: Some(1),
+                    title_bar_pixels: Some(120),
+                    ..ThresholdOverride::default()
+                },
+                ThresholdOverride {
+                    title: Some("Remote Desktop".to_string()),
+                    min_visible: Some(0.2),
+                    ..ThresholdOverride::default()
+                },
+            ]
+        );
+        assert!(Config::parse("[[override]]\nmonitr = 1").is_err());
+    }
+
+    #[test]
+    fn scope() {
+        let config = Config::parse("tool-windows = true\nowned-windows = false").unwrap();
+        assert_eq!(config.tool_windows, Some(true));
+        assert_eq!(config.owned_windows, Some(false));
+        assert_eq!(config.cloaked_windows, None);
+    }
+
+    #[test]
+    fn rules() {
+        let config = Config::parse(
+            r#"
+            [[rule]]
+            action = "include"
+            title-regex = "(?i)important"
+
+            [[rule]]
+            action = "exclude"
+            process = "mstsc.exe"
+            class = "TscShell*"
+            "#,
+        )
+        .unwrap();
+        let rules: Vec<_> = config.rules.iter().map(Rule::to_string).collect();
+        assert_eq!(
+            rules,
+            [
+                r#"include title-regex "(?i)important""#,
+                r#"exclude class "TscShell*" process "mstsc.exe""#,
+            ]
+        );
+
+        for invalid in [
+            "[[rule]]\naction = \"exclude\"",
+            "[[rule]]\naction = \"skip\"\nprocess = \"a.exe\"",
+            "[[rule]]\naction = \"exclude\"\ntitle = \"a\"\ntitle-regex = \"b\"",
+            "[[rule]]\naction = \"exclude\"\nexe = \"a.exe\"",
+        ] {
+            assert!(Config::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn goes_over_another_config() {
+        let file = Config::parse(
+            "fit = true\ncascade-step = 40\n[[rule]]\naction = \"exclude\"\ntitle = \"b\"",
+        )
+        .unwrap();
+        let command_line = Config {
+            fit: Some(false),
+            rules: vec![Rule::parse(RuleAction::Exclude, "title:a").unwrap()],
+            ..Config::default()
+        };
+        let options = command_line.over(file).plan_options().unwrap();
+        assert!(!options.fit);
+        assert_eq!(options.cascade_step, 40);
+        let rules: Vec<_> = options.rules.iter().map(Rule::to_string).collect();
+        assert_eq!(rules, [r#"exclude title "a""#, r#"exclude title "b""#]);
+        assert!(Config::parse("cascade-step = 0")
+            .unwrap()
+            .plan_options()
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_values() {
+        assert!(Config::parse(r#"strategy = "middle""#).is_err());
+        assert!(Config::parse(r#"stratgey = "center""#).is_err());
+    }
+}
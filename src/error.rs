@@ -0,0 +1,236 @@
+use std::fmt;
+
+use crate::backend::WindowId;
+
+/// Marker a backend attaches when the OS refused an operation
+///
+/// Typically the target window belongs to a process with a higher integrity level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessDenied;
+
+impl fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Access is denied")
+    }
+}
+
+impl std::error::Error for AccessDenied {}
+
+/// The window an error is about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowContext {
+    pub id: WindowId,
+    pub title: Option<String>,
+}
+
+impl WindowContext {
+    pub fn new(id: WindowId, title: Option<&str>) -> Self {
+        Self {
+            id,
+            title: title.map(str::to_string),
+        }
+    }
+}
+
+impl fmt::Display for WindowContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.title {
+            Some(title) => write!(f, "{title:?} {:?}", self.id),
+            None => write!(f, "{:?}", self.id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Top-level windows could not be enumerated at all
+    Enumerate {
+        operation: &'static str,
+        message: String,
+    },
+    /// The window title is not valid UTF-16
+    TextDecode {
+        window: WindowContext,
+        message: String,
+    },
+    /// The window or restore rect could not be read
+    RectQuery {
+        window: WindowContext,
+        message: String,
+    },
+    /// The window could not be restored from maximized
+    Restore {
+        window: WindowContext,
+        message: String,
+    },
+    /// The window could not be moved
+    Move {
+        window: WindowContext,
+        message: String,
+    },
+    /// The window was moved back but could not be maximized again
+    Maximize {
+        window: WindowContext,
+        message: String,
+    },
+    /// The window belongs to a process with a higher integrity level
+    AccessDenied {
+        window: WindowContext,
+        operation: &'static str,
+    },
+    /// The owning process is not responding to messages
+    HungWindow { window: WindowContext },
+    /// `relative:<monitor>` names a monitor that is not connected, the primary one was used
+    NoSuchMonitor { index: usize, count: usize },
+}
+
+impl Error {
+    /// Process exit code a script can branch on, stable across releases
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Enumerate { .. } => 3,
+            Error::TextDecode { .. } => 4,
+            Error::RectQuery { .. } => 5,
+            Error::Restore { .. } => 6,
+            Error::Move { .. } => 7,
+            Error::AccessDenied { .. } => 8,
+            Error::HungWindow { .. } => 9,
+            Error::Maximize { .. } => 10,
+            Error::NoSuchMonitor { .. } => 11,
+        }
+    }
+
+    pub fn window(&self) -> Option<&WindowContext> {
+        match self {
+            Error::Enumerate { .. } | Error::NoSuchMonitor { .. } => None,
+            Error::TextDecode { window, .. }
+            | Error::RectQuery { window, .. }
+            | Error::Restore { window, .. }
+            | Error::Move { window, .. }
+            | Error::Maximize { window, .. }
+            | Error::AccessDenied { window, .. }
+            | Error::HungWindow { window } => Some(window),
+        }
+    }
+
+    /// Build an error for a failed backend call, recognizing access denied
+    pub(crate) fn from_backend(
+        window: WindowContext,
+        operation: &'static str,
+        error: anyhow::Error,
+        variant: fn(WindowContext, String) -> Error,
+    ) -> Self {
+        if error.downcast_ref::<AccessDenied>().is_some() {
+            return Error::AccessDenied { window, operation };
+        }
+        variant(window, format!("{operation} failed: {error:#}"))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Enumerate { operation, message } => write!(f, "{operation} failed: {message}"),
+            Error::TextDecode { window, message } => {
+                write!(f, "Get window text failed for {window}: {message}")
+            }
+            Error::RectQuery { window, message }
+            | Error::Restore { window, message }
+            | Error::Move { window, message }
+            | Error::Maximize { window, message } => write!(f, "{window}: {message}"),
+            Error::AccessDenied { window, operation } => {
+                write!(f, "{window}: {operation} failed: access is denied")
+            }
+            Error::HungWindow { window } => write!(f, "{window}: window is not responding"),
+            Error::NoSuchMonitor { index, count } => write!(
+                f,
+                "No monitor {index} for relative:{index}, there are {count}, \
+                 used the primary monitor instead"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn window() -> WindowContext {
+        WindowContext::new(WindowId(7), Some("Notepad"))
+    }
+
+    #[test]
+    fn exit_codes_are_distinct() {
+        let window = window();
+        let errors = [
+            Error::Enumerate {
+                operation: "EnumWindows",
+                message: String::new(),
+            },
+            Error::TextDecode {
+                window: window.clone(),
+                message: String::new(),
+            },
+            Error::RectQuery {
+                window: window.clone(),
+                message: String::new(),
+            },
+            Error::Restore {
+                window: window.clone(),
+                message: String::new(),
+            },
+            Error::Move {
+                window: window.clone(),
+                message: String::new(),
+            },
+            Error::AccessDenied {
+                window: window.clone(),
+                operation: "SetWindowPos",
+            },
+            Error::HungWindow {
+                window: window.clone(),
+            },
+            Error::Maximize {
+                window,
+                message: String::new(),
+            },
+            Error::NoSuchMonitor { index: 2, count: 2 },
+        ];
+        let codes: Vec<_> = errors.iter().map(Error::exit_code).collect();
+        assert_eq!(codes, [3, 4, 5, 6, 7, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn recognizes_access_denied() {
+        let error = Error::from_backend(
+            window(),
+            "SetWindowPos",
+            anyhow!(AccessDenied),
+            |window, message| Error::Move { window, message },
+        );
+        assert_eq!(
+            error,
+            Error::AccessDenied {
+                window: window(),
+                operation: "SetWindowPos"
+            }
+        );
+    }
+
+    #[test]
+    fn keeps_other_backend_errors() {
+        let error = Error::from_backend(
+            window(),
+            "SetWindowPos",
+            anyhow!("Invalid window handle"),
+            |window, message| Error::Move { window, message },
+        );
+        assert_eq!(
+            error.to_string(),
+            "\"Notepad\" WindowId(7): SetWindowPos failed: Invalid window handle"
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use serde::Deserialize;
+
+/// Default distance from a monitor's top-left corner for [`RectCalc::left_top`]
+pub const TOP_LEFT_BOUND: i32 = 100;
+
+/// Height of the strip at the top of a window assumed to hold its title bar
+pub const TITLE_BAR_HEIGHT: i32 = 30;
+
+/// A rectangle in screen coordinates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Rect {
+    pub const fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub const fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub const fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+
+    pub const fn offset(&self, dx: i32, dy: i32) -> Self {
+        Self::new(
+            self.left + dx,
+            self.top + dy,
+            self.right + dx,
+            self.bottom + dy,
+        )
+    }
+
+    /// Same size, with the top-left corner at `(x, y)`
+    pub const fn moved_to(&self, x: i32, y: i32) -> Self {
+        self.offset(x - self.left, y - self.top)
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.left >= self.right || self.top >= self.bottom
+    }
+
+    pub const fn area(&self) -> i64 {
+        if self.is_empty() {
+            return 0;
+        }
+        self.width() as i64 * self.height() as i64
+    }
+
+    pub const fn contains_point(&self, x: i32, y: i32) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+
+    /// Whether `other` lies entirely inside this rect
+    pub const fn contains(&self, other: &Rect) -> bool {
+        other.left >= self.left
+            && other.top >= self.top
+            && other.right <= self.right
+            && other.bottom <= self.bottom
+    }
+
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect::new(
+            self.left.max(other.left),
+            self.top.max(other.top),
+            self.right.min(other.right),
+            self.bottom.min(other.bottom),
+        );
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    pub const fn size(&self) -> Size {
+        Size {
+            width: self.width(),
+            height: self.height(),
+        }
+    }
+
+    /// Same top-left corner with another size
+    pub const fn resized(&self, size: Size) -> Self {
+        Rect::new(
+            self.left,
+            self.top,
+            self.left + size.width,
+            self.top + size.height,
+        )
+    }
+
+    /// The strip at the top of the window its title bar is in
+    pub fn title_bar(&self) -> Rect {
+        Rect::new(
+            self.left,
+            self.top,
+            self.right,
+            self.top + self.height().min(TITLE_BAR_HEIGHT),
+        )
+    }
+}
+
+/// Width and height of a window, written `<width>x<height>`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Size {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Size {
+    pub const fn new(width: i32, height: i32) -> Self {
+        Self { width, height }
+    }
+
+    /// Whether a rect of this size fits inside one of `other`
+    pub const fn fits_in(&self, other: Size) -> bool {
+        self.width <= other.width && self.height <= other.height
+    }
+}
+
+impl FromStr for Size {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow!("Invalid size {s:?}, expected <width>x<height>");
+        let (width, height) = s.split_once('x').ok_or_else(invalid)?;
+        let size = Size::new(
+            width.parse().map_err(|_| invalid())?,
+            height.parse().map_err(|_| invalid())?,
+        );
+        if size.width <= 0 || size.height <= 0 {
+            bail!("Size must be positive, got {s:?}");
+        }
+        Ok(size)
+    }
+}
+
+impl TryFrom<String> for Size {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Get the display percent of a rect across all monitors
+///
+/// Monitors do not overlap, so the visible area is the sum of the intersections
+/// with each of them. Mirrored monitors are capped at fully visible.
+pub fn get_display_percent(rect: Rect, monitors: &[Rect]) -> f32 {
+    let original_area = rect.area();
+    if original_area == 0 {
+        return 0.0;
+    }
+
+    let display_area: i64 = monitors
+        .iter()
+        .filter_map(|monitor| rect.intersection(monitor))
+        .map(|visible| visible.area())
+        .sum();
+
+    (display_area as f32 / original_area as f32).min(1.0)
+}
+
+/// Pixels of the title bar strip, measured along it, that lie on some monitor
+pub fn visible_title_bar(rect: Rect, monitors: &[Rect]) -> i32 {
+    monitors
+        .iter()
+        .filter_map(|monitor| rect.title_bar().intersection(monitor))
+        .map(|visible| visible.width())
+        .sum::<i32>()
+        .min(rect.width())
+}
+
+pub trait RectCalc {
+    /// Whether the top-left corner sits just inside the top-left corner of a monitor
+    ///
+    /// Monitors may have any origin, including negative ones left of or above the primary.
+    fn left_top(&self, monitors: &[Rect]) -> bool {
+        self.left_top_within(monitors, TOP_LEFT_BOUND)
+    }
+
+    /// Same as [`left_top`](RectCalc::left_top) with another distance than [`TOP_LEFT_BOUND`]
+    fn left_top_within(&self, monitors: &[Rect], bound: i32) -> bool;
+}
+
+impl RectCalc for Rect {
+    fn left_top_within(&self, monitors: &[Rect], bound: i32) -> bool {
+        monitors.iter().any(|monitor| {
+            let (dx, dy) = (self.left - monitor.left, self.top - monitor.top);
+            (0..=bound).contains(&dx) && (0..=bound).contains(&dy)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIMARY: Rect = Rect::new(0, 0, 1920, 1080);
+    const SECONDARY: Rect = Rect::new(1920, 0, 3840, 1080);
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!("640x480".parse::<Size>().unwrap(), Size::new(640, 480));
+        assert_eq!(Size::new(640, 480).to_string(), "640x480");
+        for invalid in ["640", "640x", "x480", "0x480", "-640x480", "640*480"] {
+            assert!(invalid.parse::<Size>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn display_percent_fully_visible() {
+        let rect = Rect::new(100, 100, 500, 400);
+        assert_eq!(get_display_percent(rect, &[PRIMARY]), 1.0);
+    }
+
+    #[test]
+    fn display_percent_partially_visible() {
+        let rect = Rect::new(1720, 0, 2120, 100);
+        assert_eq!(get_display_percent(rect, &[PRIMARY]), 0.5);
+    }
+
+    #[test]
+    fn display_percent_off_screen() {
+        let rect = Rect::new(-500, 100, -100, 400);
+        assert_eq!(get_display_percent(rect, &[PRIMARY]), 0.0);
+    }
+
+    #[test]
+    fn display_percent_empty_rect() {
+        let rect = Rect::new(100, 100, 100, 400);
+        assert_eq!(get_display_percent(rect, &[PRIMARY]), 0.0);
+    }
+
+    #[test]
+    fn display_percent_on_secondary_monitor() {
+        let rect = Rect::new(2500, 100, 3100, 500);
+        assert_eq!(get_display_percent(rect, &[PRIMARY]), 0.0);
+        assert_eq!(get_display_percent(rect, &[PRIMARY, SECONDARY]), 1.0);
+    }
+
+    #[test]
+    fn display_percent_spanning_monitors() {
+        let rect = Rect::new(1720, 0, 2120, 100);
+        assert_eq!(get_display_percent(rect, &[PRIMARY, SECONDARY]), 1.0);
+    }
+
+    #[test]
+    fn display_percent_mirrored_monitors() {
+        let rect = Rect::new(100, 100, 500, 400);
+        assert_eq!(get_display_percent(rect, &[PRIMARY, PRIMARY]), 1.0);
+    }
+
+    #[test]
+    fn display_percent_in_layout_hole() {
+        // L-shaped desktop: a second monitor above and to the right of the primary
+        let upper = Rect::new(1920, -1080, 3840, 0);
+        let monitors = [PRIMARY, upper];
+        let in_hole = Rect::new(2200, 200, 3000, 800);
+        let above_primary = Rect::new(200, -900, 1000, -300);
+        assert_eq!(get_display_percent(in_hole, &monitors), 0.0);
+        assert_eq!(get_display_percent(above_primary, &monitors), 0.0);
+        assert_eq!(
+            get_display_percent(Rect::new(2200, -900, 3000, -300), &monitors),
+            1.0
+        );
+    }
+
+    #[test]
+    fn display_percent_on_negative_monitor() {
+        let left = Rect::new(-2560, -360, 0, 1080);
+        let rect = Rect::new(-2000, -200, -1200, 400);
+        assert_eq!(get_display_percent(rect, &[PRIMARY, left]), 1.0);
+    }
+
+    #[test]
+    fn left_top_bounds() {
+        let monitors = [PRIMARY];
+        assert!(Rect::new(0, 0, 10, 10).left_top(&monitors));
+        assert!(Rect::new(TOP_LEFT_BOUND, TOP_LEFT_BOUND, 5000, 5000).left_top(&monitors));
+        assert!(!Rect::new(-1, 0, 10, 10).left_top(&monitors));
+        assert!(!Rect::new(0, TOP_LEFT_BOUND + 1, 10, 200).left_top(&monitors));
+    }
+
+    #[test]
+    fn left_top_of_negative_monitor() {
+        let monitors = [PRIMARY, Rect::new(-2560, -360, 0, 1080)];
+        assert!(Rect::new(-2500, -300, 5000, 5000).left_top(&monitors));
+        assert!(!Rect::new(-2600, -300, 5000, 5000).left_top(&monitors));
+        assert!(!Rect::new(-1, 0, 10, 10).left_top(&monitors));
+    }
+
+    #[test]
+    fn left_top_with_custom_bound() {
+        let rect = Rect::new(150, 20, 900, 600);
+        assert!(!rect.left_top(&[PRIMARY]));
+        assert!(rect.left_top_within(&[PRIMARY], 200));
+        assert!(!Rect::new(1, 1, 10, 10).left_top_within(&[PRIMARY], 0));
+    }
+
+    #[test]
+    fn title_bar_visible_across_monitors() {
+        let spanning = Rect::new(1500, 10, 2300, 600);
+        assert_eq!(visible_title_bar(spanning, &[PRIMARY, SECONDARY]), 800);
+        assert_eq!(visible_title_bar(spanning, &[PRIMARY]), 420);
+
+        let caption_above = Rect::new(100, -40, 900, 600);
+        assert_eq!(visible_title_bar(caption_above, &[PRIMARY]), 0);
+        let caption_clipped = Rect::new(100, -20, 900, 600);
+        assert_eq!(visible_title_bar(caption_clipped, &[PRIMARY]), 800);
+    }
+}
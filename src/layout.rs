@@ -0,0 +1,340 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::bail;
+use serde::Deserialize;
+
+use crate::backend::Monitor;
+use crate::geometry::{Rect, TITLE_BAR_HEIGHT};
+use crate::placement::work_area_at;
+
+/// How several rescued windows are arranged relative to each other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Layout {
+    /// Every window goes where the placement strategy puts it, possibly on top of each other
+    #[default]
+    Stack,
+    /// Each window is offset from the previous one so every title bar stays visible
+    Cascade,
+    /// Non-overlapping tiles on the target monitor, shrinking windows that do not fit
+    Grid,
+    /// The largest free space left by on-screen windows, cascading when there is none
+    FreeSpace,
+}
+
+/// Cell of a grid layout a window was assigned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub row: usize,
+    pub column: usize,
+}
+
+pub const DEFAULT_CASCADE_STEP: i32 = 32;
+
+impl FromStr for Layout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "stack" => Layout::Stack,
+            "cascade" => Layout::Cascade,
+            "grid" => Layout::Grid,
+            "free-space" => Layout::FreeSpace,
+            _ => bail!("Unknown layout {s:?}, expected stack, cascade, grid or free-space"),
+        })
+    }
+}
+
+impl TryFrom<String> for Layout {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layout::Stack => write!(f, "stack"),
+            Layout::Cascade => write!(f, "cascade"),
+            Layout::Grid => write!(f, "grid"),
+            Layout::FreeSpace => write!(f, "free-space"),
+        }
+    }
+}
+
+/// Offset the n-th window by n steps down and right from where it was placed
+///
+/// When a window would leave the work area it was placed on, the cascade starts over from
+/// the placed position, so the result only depends on the order of `targets`. Windows too
+/// large to be offset by a step inside the area only keep the start of their title bar in
+/// it, so their title bars still stagger.
+pub fn cascade(targets: &mut [Rect], monitors: &[Monitor], step: i32) {
+    let mut offset = 0;
+    for target in targets.iter_mut() {
+        let area = work_area_at(monitors, target.left, target.top);
+        let mut candidate = target.offset(offset * step, offset * step);
+        let roomy =
+            target.width() + step <= area.width() && target.height() + step <= area.height();
+        let (right, bottom) = if roomy {
+            (candidate.right, candidate.bottom)
+        } else {
+            (
+                candidate.left + TITLE_BAR_HEIGHT,
+                candidate.top + TITLE_BAR_HEIGHT,
+            )
+        };
+        if offset > 0 && (right > area.right || bottom > area.bottom) {
+            offset = 0;
+            candidate = *target;
+        }
+        *target = candidate;
+        offset += 1;
+    }
+}
+
+/// Assign each window a cell of a near-square grid covering `area`
+///
+/// Windows keep their size when it fits the cell and are scaled down, keeping their
+/// aspect ratio, when it does not. Cells are filled row by row in the order of `targets`.
+pub fn grid(targets: &mut [Rect], area: Rect) -> Vec<Tile> {
+    let count = targets.len();
+    if count == 0 {
+        return Vec::new();
+    }
+    let columns = (count as f64).sqrt().ceil() as usize;
+    let rows = count.div_ceil(columns);
+    let cell_width = area.width() / columns as i32;
+    let cell_height = area.height() / rows as i32;
+
+    let mut tiles = Vec::with_capacity(count);
+    for (index, target) in targets.iter_mut().enumerate() {
+        let tile = Tile {
+            row: index / columns,
+            column: index % columns,
+        };
+        let scale = (cell_width as f64 / target.width() as f64)
+            .min(cell_height as f64 / target.height() as f64)
+            .min(1.0);
+        let width = (target.width() as f64 * scale) as i32;
+        let height = (target.height() as f64 * scale) as i32;
+        let left = area.left + tile.column as i32 * cell_width;
+        let top = area.top + tile.row as i32 * cell_height;
+        *target = Rect::new(left, top, left + width, top + height);
+        tiles.push(tile);
+    }
+    tiles
+}
+
+/// Maximal rectangles of `area` not covered by any of `occupied`
+///
+/// Each free rect is as large as it can be, so free rects may overlap each other.
+pub fn free_rects(area: Rect, occupied: &[Rect]) -> Vec<Rect> {
+    let mut free = vec![area];
+    for used in occupied {
+        let mut split = Vec::with_capacity(free.len());
+        for rect in free {
+            let Some(hit) = rect.intersection(used) else {
+                split.push(rect);
+                continue;
+            };
+            let pieces = [
+                Rect::new(rect.left, rect.top, hit.left, rect.bottom),
+                Rect::new(hit.right, rect.top, rect.right, rect.bottom),
+                Rect::new(rect.left, rect.top, rect.right, hit.top),
+                Rect::new(rect.left, hit.bottom, rect.right, rect.bottom),
+            ];
+            split.extend(pieces.into_iter().filter(|piece| !piece.is_empty()));
+        }
+
+        // Drop rects inside another one, keeping the first of identical ones
+        free = split
+            .iter()
+            .enumerate()
+            .filter(|&(i, rect)| {
+                !split
+                    .iter()
+                    .enumerate()
+                    .any(|(j, other)| j != i && other.contains(rect) && (other != rect || j < i))
+            })
+            .map(|(_, rect)| *rect)
+            .collect();
+    }
+    free
+}
+
+/// Move each window into the largest free rect of its work area that it fits in
+///
+/// Windows placed earlier count as occupied for later ones. Windows that fit nowhere are
+/// cascaded from where they were placed.
+pub fn free_space(targets: &mut [Rect], monitors: &[Monitor], occupied: &[Rect], step: i32) {
+    let mut occupied = occupied.to_vec();
+    let mut crowded = Vec::new();
+    for (index, target) in targets.iter_mut().enumerate() {
+        let area = work_area_at(monitors, target.left, target.top);
+        let space = free_rects(area, &occupied)
+            .into_iter()
+            .filter(|free| free.width() >= target.width() && free.height() >= target.height())
+            .max_by_key(|free| free.area());
+        match space {
+            Some(free) => {
+                *target = target.moved_to(free.left, free.top);
+                occupied.push(*target);
+            }
+            None => crowded.push(index),
+        }
+    }
+
+    let mut cascaded: Vec<_> = crowded.iter().map(|&index| targets[index]).collect();
+    cascade(&mut cascaded, monitors, step);
+    for (index, target) in crowded.into_iter().zip(cascaded) {
+        targets[index] = target;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitors() -> Vec<Monitor> {
+        vec![Monitor {
+            rect: Rect::new(0, 0, 1920, 1080),
+            work_area: Rect::new(0, 40, 1920, 1080),
+            primary: true,
+        }]
+    }
+
+    #[test]
+    fn parses_layout_names() {
+        for name in ["stack", "cascade", "grid", "free-space"] {
+            assert_eq!(name.parse::<Layout>().unwrap().to_string(), name);
+        }
+        assert!("pile".parse::<Layout>().is_err());
+    }
+
+    #[test]
+    fn cascades_from_first_position() {
+        let mut targets = [
+            Rect::new(0, 40, 800, 640),
+            Rect::new(0, 40, 600, 440),
+            Rect::new(0, 40, 1000, 540),
+        ];
+
+        cascade(&mut targets, &monitors(), 30);
+
+        assert_eq!(
+            targets,
+            [
+                Rect::new(0, 40, 800, 640),
+                Rect::new(30, 70, 630, 470),
+                Rect::new(60, 100, 1060, 600),
+            ]
+        );
+    }
+
+    #[test]
+    fn wraps_within_work_area() {
+        let mut targets = [Rect::new(0, 40, 800, 940); 6];
+
+        cascade(&mut targets, &monitors(), 50);
+
+        let tops: Vec<_> = targets.iter().map(|target| target.top).collect();
+        assert_eq!(tops, [40, 90, 140, 40, 90, 140]);
+        assert!(targets.iter().all(|target| target.bottom <= 1080));
+    }
+
+    #[test]
+    fn staggers_windows_larger_than_work_area() {
+        let mut targets = [Rect::new(0, 40, 2000, 1140); 3];
+
+        cascade(&mut targets, &monitors(), 32);
+
+        let corners: Vec<_> = targets
+            .iter()
+            .map(|target| (target.left, target.top))
+            .collect();
+        assert_eq!(corners, [(0, 40), (32, 72), (64, 104)]);
+    }
+
+    #[test]
+    fn grid_tiles_row_by_row() {
+        let mut targets = [Rect::new(0, 0, 400, 300); 5];
+
+        let tiles = grid(&mut targets, Rect::new(0, 40, 1920, 1080));
+
+        let cells: Vec<_> = tiles.iter().map(|tile| (tile.row, tile.column)).collect();
+        assert_eq!(cells, [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1)]);
+        assert_eq!(targets[0], Rect::new(0, 40, 400, 340));
+        assert_eq!(targets[2], Rect::new(1280, 40, 1680, 340));
+        assert_eq!(targets[4], Rect::new(640, 560, 1040, 860));
+    }
+
+    #[test]
+    fn grid_shrinks_keeping_aspect_ratio() {
+        let mut targets = [Rect::new(0, 0, 2560, 1440), Rect::new(0, 0, 600, 1200)];
+
+        grid(&mut targets, Rect::new(0, 0, 1920, 1080));
+
+        assert_eq!(targets[0], Rect::new(0, 0, 960, 540));
+        assert_eq!(targets[1], Rect::new(960, 0, 1500, 1080));
+    }
+
+    #[test]
+    fn grid_tiles_do_not_overlap() {
+        let mut targets = [Rect::new(0, 0, 1600, 900); 12];
+
+        grid(&mut targets, Rect::new(-1920, 0, 0, 1040));
+
+        for (i, a) in targets.iter().enumerate() {
+            assert!(a.left >= -1920 && a.right <= 0 && a.top >= 0 && a.bottom <= 1040);
+            for b in &targets[i + 1..] {
+                assert_eq!(a.intersection(b), None);
+            }
+        }
+    }
+
+    #[test]
+    fn free_rects_around_a_window() {
+        let area = Rect::new(0, 0, 1000, 800);
+        let mut free = free_rects(area, &[Rect::new(200, 100, 600, 500)]);
+        free.sort_by_key(|rect| (rect.left, rect.top, rect.right, rect.bottom));
+
+        assert_eq!(
+            free,
+            [
+                Rect::new(0, 0, 200, 800),
+                Rect::new(0, 0, 1000, 100),
+                Rect::new(0, 500, 1000, 800),
+                Rect::new(600, 0, 1000, 800),
+            ]
+        );
+        assert!(free_rects(area, &[area]).is_empty());
+    }
+
+    #[test]
+    fn free_space_avoids_occupied_and_placed_windows() {
+        let occupied = [Rect::new(0, 40, 1000, 1080)];
+        let mut targets = [Rect::new(0, 40, 600, 440), Rect::new(0, 40, 600, 440)];
+
+        free_space(&mut targets, &monitors(), &occupied, 32);
+
+        assert_eq!(targets[0], Rect::new(1000, 40, 1600, 440));
+        assert_eq!(targets[1], Rect::new(1000, 440, 1600, 840));
+        assert_eq!(targets[0].intersection(&occupied[0]), None);
+    }
+
+    #[test]
+    fn free_space_cascades_without_room() {
+        let occupied = [Rect::new(0, 0, 1920, 1080)];
+        let mut targets = [Rect::new(0, 40, 600, 440); 2];
+
+        free_space(&mut targets, &monitors(), &occupied, 32);
+
+        assert_eq!(
+            targets,
+            [Rect::new(0, 40, 600, 440), Rect::new(32, 72, 632, 472)]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! MOSWB: Move off-screen window back
+
+pub mod backend;
+pub mod config;
+pub mod error;
+pub mod geometry;
+pub mod layout;
+pub mod memory;
+pub mod placement;
+pub mod plan;
+pub mod report;
+pub mod rescue;
+pub mod rules;
+pub mod session;
+pub mod state;
+pub mod undo;
+pub mod window;
+
+use anyhow::Result;
+
+pub use backend::{Monitor, WindowId, WindowSystem};
+pub use config::Config;
+pub use error::Error;
+pub use geometry::{get_display_percent, Rect, RectCalc, Size};
+pub use layout::{Layout, Tile};
+pub use memory::SizeMemory;
+pub use placement::PlacementStrategy;
+pub use plan::{apply, apply_move, Action, PlanOptions, PlannedMove, RelocationPlan};
+pub use report::{Format, Report, SkipReason, Skipped};
+pub use rescue::{
+    classify_parking, is_off_screen, is_reachable, rescue_windows, Detection, Parking,
+    ThresholdOverride, Thresholds,
+};
+pub use rules::{Rule, RuleAction, Target};
+pub use session::{Pass, State};
+pub use undo::UndoLog;
+pub use window::{Scope, Snapshot, WindowInfo, WindowKind};
+
+pub fn wide_string_to_string(wide_string: &[u16]) -> Result<String> {
+    let string = if let Some(null_pos) = wide_string.iter().position(|pos| *pos == 0) {
+        String::from_utf16(&wide_string[..null_pos])?
+    } else {
+        String::from_utf16(wide_string)?
+    };
+
+    Ok(string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_string_stops_at_nul() {
+        let wide: Vec<u16> = "Notepad\0garbage".encode_utf16().collect();
+        assert_eq!(wide_string_to_string(&wide).unwrap(), "Notepad");
+    }
+
+    #[test]
+    fn wide_string_without_nul() {
+        let wide: Vec<u16> = "Notepad".encode_utf16().collect();
+        assert_eq!(wide_string_to_string(&wide).unwrap(), "Notepad");
+    }
+
+    #[test]
+    fn wide_string_rejects_lone_surrogate() {
+        assert!(wide_string_to_string(&[0x0041, 0xD800, 0x0042]).is_err());
+    }
+}
@@ -1,153 +1,703 @@
-use anyhow::Context;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
 use anyhow::Result;
-use windows::core::HRESULT;
-use windows::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
-use windows::Win32::UI::WindowsAndMessaging::{
-    EnumWindows, GetSystemMetrics, GetWindowRect, GetWindowTextLengthW, GetWindowTextW, IsIconic,
-    IsWindowVisible, IsZoomed, SetWindowPos, ShowWindow, SM_CXSCREEN, SM_CYSCREEN, SWP_NOACTIVATE,
-    SWP_NOSIZE, SWP_NOZORDER, SW_RESTORE,
+use clap::{ArgGroup, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use moswb::{
+    Config, Detection, Format, Layout, PlacementStrategy, PlanOptions, Rule, RuleAction, Size,
+    Target,
 };
 
-unsafe fn get_screen_size() -> (i32, i32) {
-    (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN))
+/// Move off-screen windows back
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Without a subcommand, every lost window is moved back like with `fix`
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    options: Options,
 }
 
-fn wide_string_to_string(wide_string: &[u16]) -> Result<String> {
-    let string = if let Some(null_pos) = wide_string.iter().position(|pos| *pos == 0) {
-        String::from_utf16(&wide_string[..null_pos])?
-    } else {
-        String::from_utf16(wide_string)?
-    };
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+enum Command {
+    /// List the windows that would be moved, and where, without moving them
+    #[command(alias = "scan")]
+    List,
+    /// Move every lost window back to a work area
+    Fix,
+    /// Move only the windows matching every criterion given
+    Move(MoveArgs),
+    /// Put the windows moved by the last run back where they were
+    Restore,
+    /// Keep moving lost windows back until interrupted
+    Watch {
+        /// Seconds between two passes
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+#[command(group(ArgGroup::new("target").required(true).multiple(true)))]
+struct MoveArgs {
+    /// Case-insensitive glob on the title, like "*Notepad"
+    #[arg(long, group = "target")]
+    title: Option<String>,
+    /// Window handle, decimal or 0x-prefixed hex
+    #[arg(long, group = "target")]
+    hwnd: Option<String>,
+    /// Case-insensitive glob on the executable name, like notepad.exe
+    #[arg(long, group = "target")]
+    process: Option<String>,
+    /// Move the windows even when they are on screen
+    #[arg(long)]
+    force: bool,
+}
+
+impl MoveArgs {
+    fn target(&self) -> Result<Target> {
+        let mut target = Target::default();
+        if let Some(title) = &self.title {
+            target.set_title(title)?;
+        }
+        if let Some(hwnd) = &self.hwnd {
+            target.set_hwnd(hwnd)?;
+        }
+        if let Some(process) = &self.process {
+            target.set_process(process)?;
+        }
+        Ok(target)
+    }
+}
 
-    Ok(string)
+/// Options shared by every subcommand, they take precedence over the config file
+#[derive(Debug, Default, Args)]
+struct Options {
+    /// Plan without moving any window
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// top-left, center, nearest-edge, cursor or relative:<monitor>
+    #[arg(long, global = true)]
+    strategy: Option<PlacementStrategy>,
+    /// stack, cascade, grid or free-space
+    #[arg(long, global = true)]
+    layout: Option<Layout>,
+    /// Offset between cascaded windows, 32 by default
+    #[arg(
+        long,
+        global = true,
+        value_name = "PIXELS",
+        allow_negative_numbers = true
+    )]
+    cascade_step: Option<i32>,
+    /// Never shrink below this size, 320x240 by default
+    #[arg(long, global = true, value_name = "WIDTHxHEIGHT")]
+    min_size: Option<Size>,
+    /// How lost windows are detected: area or reachable
+    #[arg(long, global = true)]
+    detection: Option<Detection>,
+    /// Leave windows with more than this fraction on screen, 0.5 by default
+    #[arg(long, global = true, value_name = "FRACTION")]
+    min_visible: Option<f32>,
+    /// Leave windows this close to a monitor's top-left corner, 100 by default
+    #[arg(
+        long,
+        global = true,
+        value_name = "PIXELS",
+        allow_negative_numbers = true
+    )]
+    top_left_bound: Option<i32>,
+    /// Leave windows with this much title bar on screen, instead of --min-visible
+    #[arg(
+        long,
+        global = true,
+        value_name = "PIXELS",
+        allow_negative_numbers = true
+    )]
+    title_bar_pixels: Option<i32>,
+    /// Rules given before the subcommand, [`parse`] adds them to every subcommand too
+    #[command(flatten)]
+    rule_args: RuleArgs,
+    /// `--include` and `--exclude` in the order given, those before the subcommand first
+    #[arg(skip)]
+    rules: Vec<Rule>,
+    /// Switches given before the subcommand, [`parse`] adds them to every subcommand too
+    #[command(flatten)]
+    switch_args: SwitchArgs,
+    /// The switches given, those after the subcommand going over those before it
+    #[arg(skip)]
+    switches: Config,
+    /// text, or tsv for scripts
+    #[arg(long, global = true, default_value_t)]
+    format: Format,
+    /// Print the effective settings before the windows, and list untitled windows too
+    #[arg(short, long, global = true, conflicts_with = "quiet")]
+    verbose: bool,
+    /// Only print errors
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    /// Defaults to `%APPDATA%\moswb\config.toml` when that file exists
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
 }
 
-fn get_window_text(hwnd: HWND) -> Result<String> {
-    let text_length = unsafe { GetWindowTextLengthW(hwnd) };
-    let mut wide_buffer = vec![0u16; (text_length + 1) as usize];
-    unsafe { GetWindowTextW(hwnd, &mut wide_buffer) };
-    wide_string_to_string(&wide_buffer)
-        .with_context(|| format!("Failed to convert wide string to string: {:?}", wide_buffer))
+// Not global, clap would keep only the rules given after the subcommand. Plain comments,
+// a doc comment here would replace the help of every subcommand.
+#[derive(Debug, Default, Args)]
+struct RuleArgs {
+    /// Only rescue windows matching a rule, like title:*Notepad*
+    #[arg(long = "include", value_name = "RULE", value_parser = include_rule)]
+    includes: Vec<Rule>,
+    /// Never rescue windows matching a rule, like process:mstsc.exe
+    #[arg(long = "exclude", value_name = "RULE", value_parser = exclude_rule)]
+    excludes: Vec<Rule>,
 }
 
-/// Get the display percent of a rect on the screen
-fn get_display_percent(rect: RECT, width: i32, height: i32) -> f32 {
-    let x_min = rect.left.max(0);
-    let y_min = rect.top.max(0);
-    let x_max = rect.right.min(width);
-    let y_max = rect.bottom.min(height);
-    if x_min >= x_max || y_min >= y_max {
-        return 0.0;
+impl RuleArgs {
+    /// `--include` and `--exclude` in the order given
+    ///
+    /// Clap keeps the two options apart, the indices of their values in `matches` tell how
+    /// they interleave.
+    fn ordered(&self, matches: &ArgMatches) -> Vec<Rule> {
+        let indices = |id| matches.indices_of(id).into_iter().flatten();
+        let mut rules: Vec<_> = indices("includes")
+            .zip(&self.includes)
+            .chain(indices("excludes").zip(&self.excludes))
+            .collect();
+        rules.sort_by_key(|(index, _)| *index);
+        rules.into_iter().map(|(_, rule)| rule.clone()).collect()
     }
+}
 
-    let display_width = (x_max - x_min) as f32;
-    let display_height = (y_max - y_min) as f32;
+// Options turned on with `--x` and off with `--no-x`, the last one given wins. Not global
+// either, clap would keep both of a pair given on each side of the subcommand.
+#[derive(Debug, Default, Args)]
+struct SwitchArgs {
+    /// Shrink windows larger than their destination work area
+    #[arg(long, overrides_with = "no_fit")]
+    fit: bool,
+    /// Never shrink windows, even with fit in the config file
+    #[arg(long, overrides_with = "fit")]
+    no_fit: bool,
+    /// Keep the aspect ratio of shrunk windows
+    #[arg(long, overrides_with = "no_keep_aspect")]
+    keep_aspect: bool,
+    /// Let shrunk windows change their aspect ratio
+    #[arg(long, overrides_with = "keep_aspect")]
+    no_keep_aspect: bool,
+    /// Also rescue tool windows
+    #[arg(long, overrides_with = "no_tool_windows")]
+    tool_windows: bool,
+    /// Leave tool windows alone
+    #[arg(long, overrides_with = "tool_windows")]
+    no_tool_windows: bool,
+    /// Also rescue owned windows whose owner is not listed
+    #[arg(long, overrides_with = "no_owned_windows")]
+    owned_windows: bool,
+    /// Leave owned windows alone
+    #[arg(long, overrides_with = "owned_windows")]
+    no_owned_windows: bool,
+    /// Also rescue windows hidden by DWM
+    #[arg(long, overrides_with = "no_cloaked_windows")]
+    cloaked_windows: bool,
+    /// Leave windows hidden by DWM alone
+    #[arg(long, overrides_with = "cloaked_windows")]
+    no_cloaked_windows: bool,
+    /// Also rescue click-through and fully transparent overlays
+    #[arg(long, overrides_with = "no_transparent_windows")]
+    transparent_windows: bool,
+    /// Leave click-through and fully transparent overlays alone
+    #[arg(long, overrides_with = "transparent_windows")]
+    no_transparent_windows: bool,
+}
 
-    let original_width = (rect.right - rect.left) as f32;
-    let original_height = (rect.bottom - rect.top) as f32;
+impl SwitchArgs {
+    /// The switches given, to go over the config file
+    fn config(&self) -> Config {
+        Config {
+            fit: switch(self.fit, self.no_fit),
+            keep_aspect: switch(self.keep_aspect, self.no_keep_aspect),
+            tool_windows: switch(self.tool_windows, self.no_tool_windows),
+            owned_windows: switch(self.owned_windows, self.no_owned_windows),
+            cloaked_windows: switch(self.cloaked_windows, self.no_cloaked_windows),
+            transparent_windows: switch(self.transparent_windows, self.no_transparent_windows),
+            ..Config::default()
+        }
+    }
+}
 
-    if original_height <= 0.0 || original_width <= 0.0 {
-        return 0.0;
+/// `--x` or `--no-x`, or unset to leave it to the config file
+fn switch(on: bool, off: bool) -> Option<bool> {
+    match (on, off) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
     }
+}
 
-    (display_width * display_height) / (original_width * original_height)
+fn include_rule(condition: &str) -> Result<Rule> {
+    Rule::parse(RuleAction::Include, condition)
 }
 
-trait RectCalc {
-    fn left_top(&self) -> bool;
+fn exclude_rule(condition: &str) -> Result<Rule> {
+    Rule::parse(RuleAction::Exclude, condition)
 }
 
-impl RectCalc for RECT {
-    fn left_top(&self) -> bool {
-        (self.left >= 0 && self.left <= TOP_LEFT_BOUND)
-            && (self.top >= 0 && self.top <= TOP_LEFT_BOUND)
+/// [`Cli`] with the rule options and switches accepted after the subcommand too
+fn command() -> clap::Command {
+    Cli::command()
+        .mut_subcommands(RuleArgs::augment_args)
+        .mut_subcommands(SwitchArgs::augment_args)
+}
+
+fn parse(args: impl IntoIterator<Item = impl Into<OsString> + Clone>) -> Result<Cli, clap::Error> {
+    let matches = command().try_get_matches_from(args)?;
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    cli.options.rules = cli.options.rule_args.ordered(&matches);
+    cli.options.switches = cli.options.switch_args.config();
+    if let Some((_, matches)) = matches.subcommand() {
+        let after = RuleArgs::from_arg_matches(matches)?;
+        cli.options.rules.extend(after.ordered(matches));
+        let after = SwitchArgs::from_arg_matches(matches)?;
+        cli.options.switches = after.config().over(cli.options.switches);
     }
+    Ok(cli)
 }
 
-unsafe extern "system" fn enum_window_callback(hwnd: HWND, _lparam: LPARAM) -> BOOL {
-    let is_visible = IsWindowVisible(hwnd).as_bool();
-    if !is_visible {
-        return BOOL(1);
+impl Options {
+    /// The options given, to go over the config file
+    fn config(&self) -> Config {
+        Config {
+            strategy: self.strategy,
+            layout: self.layout,
+            cascade_step: self.cascade_step,
+            min_size: self.min_size,
+            detection: self.detection,
+            min_visible: self.min_visible,
+            top_left_bound: self.top_left_bound,
+            title_bar_pixels: self.title_bar_pixels,
+            rules: self.rules.clone(),
+            ..Config::default()
+        }
+        .over(self.switches.clone())
     }
+}
 
-    let is_minimize = IsIconic(hwnd).as_bool();
-    if is_minimize {
-        return BOOL(1);
+/// Merge the config file under the command line options
+fn plan_options(cli: &Cli, config: Config) -> Result<PlanOptions> {
+    let (target, force) = match &cli.command {
+        Some(Command::Move(args)) => (Some(args.target()?), args.force),
+        _ => (None, false),
+    };
+    Ok(PlanOptions {
+        target,
+        force,
+        ..cli.options.config().over(config).plan_options()?
+    })
+}
+
+fn print_settings(options: &PlanOptions) {
+    println!("Strategy: {} Layout: {}", options.strategy, options.layout);
+    match &options.target {
+        Some(target) if options.force => println!("Moving {target}, even when on screen"),
+        Some(target) => println!("Moving {target}"),
+        None => println!("Rescuing {}", options.scope),
+    }
+    for (index, rule) in options.rules.iter().enumerate() {
+        println!("Rule {}: {rule}", index + 1);
     }
+    println!("Detection: {}", options.detection);
+    if options.detection == Detection::Area {
+        println!("Windows are {}", options.thresholds);
+        for threshold_override in &options.overrides {
+            println!("Override {threshold_override}");
+        }
+    }
+}
 
-    let window_text = match get_window_text(hwnd) {
-        Ok(text) => text,
-        Err(e) => {
-            eprintln!("Get window text failed for {:?}: {:?}", hwnd, e);
-            return BOOL(0);
+fn usage_error(e: anyhow::Error) -> ! {
+    eprintln!("{e:#}");
+    std::process::exit(2);
+}
+
+/// Read the command line and config file, exiting on usage errors
+fn setup() -> (Cli, PlanOptions) {
+    let cli = parse(std::env::args_os()).unwrap_or_else(|e| e.exit());
+    let plan_options = Config::find(cli.options.config.as_deref())
+        .and_then(|config| plan_options(&cli, config))
+        .unwrap_or_else(|e| usage_error(e));
+    if cli.options.verbose && cli.options.format == Format::Text {
+        print_settings(&plan_options);
+    }
+    (cli, plan_options)
+}
+
+#[cfg(windows)]
+fn main() {
+    use moswb::{session, Error, Pass, Report, State};
+    use std::time::Duration;
+
+    let (cli, plan_options) = setup();
+    let pass = match cli.command {
+        Some(Command::Restore) => Pass::Restore,
+        _ => Pass::Rescue,
+    };
+    // `list` plans like `fix` without moving anything
+    let dry_run = cli.options.dry_run || cli.command == Some(Command::List);
+    let print_moves = cli.options.format == Format::Text && !cli.options.quiet;
+    let print_report = |report: &Report| {
+        if cli.options.quiet {
+            return;
+        }
+        match cli.options.format {
+            Format::Text if cli.options.verbose => print!("{report:#}"),
+            Format::Text => print!("{report}"),
+            Format::Tsv => print!("{}", report.tsv()),
         }
     };
 
-    if window_text.is_empty() {
-        return BOOL(1);
+    let mut system = moswb::backend::win32::Win32;
+    let (mut state, warnings) = State::load();
+    for e in warnings {
+        eprintln!("Warning: {e:#}");
+    }
+    let mut run = |state: &mut State| {
+        let original = state.clone();
+        let report = session::run(
+            &mut system,
+            pass,
+            dry_run,
+            &plan_options,
+            state,
+            |planned| {
+                if print_moves {
+                    println!("{planned}");
+                }
+            },
+        );
+        for e in state.save(&original) {
+            eprintln!("Warning: {e:#}");
+        }
+        report
+    };
+
+    if let Some(Command::Watch { interval }) = cli.command {
+        // Only passes that moved or failed to move windows are reported
+        loop {
+            match run(&mut state) {
+                Ok(report) if !report.moved.is_empty() || report.has_failures() => {
+                    print_report(&report)
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("{e}"),
+            }
+            std::thread::sleep(Duration::from_secs(interval));
+        }
     }
 
-    let mut rect = RECT::default();
-    match GetWindowRect(hwnd, &mut rect) {
-        Ok(_) => (),
+    match run(&mut state) {
+        Ok(report) => {
+            print_report(&report);
+            if report
+                .failed
+                .iter()
+                .any(|e| matches!(e, Error::AccessDenied { .. }))
+            {
+                eprintln!("Tip: Try running as administrator.");
+            }
+            std::process::exit(report.exit_code());
+        }
         Err(e) => {
-            eprintln!("GetWindowRect failed for {:?}: {:?}", hwnd, e);
-            return BOOL(0);
+            eprintln!("{e}");
+            std::process::exit(e.exit_code());
         }
     }
+}
 
-    if rect.left_top() {
-        return BOOL(1);
+#[cfg(not(windows))]
+fn main() {
+    // The command line and config file are still checked
+    setup();
+    eprintln!("moswb can only move windows on Windows");
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use moswb::Scope;
+
+    fn args(args: &[&str]) -> Result<Cli, clap::Error> {
+        parse(std::iter::once("moswb").chain(args.iter().copied()))
     }
 
-    let (width, height) = get_screen_size();
-    let display_percent = get_display_percent(rect, width, height);
-    if display_percent > 0.5 {
-        return BOOL(1);
+    #[test]
+    fn verify_cli() {
+        let command = command();
+        command.clone().debug_assert();
+        // Options added to the subcommands leave their help alone
+        let about = command.find_subcommand("fix").unwrap().get_about().unwrap();
+        assert_eq!(
+            about.to_string(),
+            "Move every lost window back to a work area"
+        );
     }
 
-    let is_maximize = IsZoomed(hwnd).as_bool();
-    if is_maximize {
-        match ShowWindow(hwnd, SW_RESTORE).ok() {
-            Ok(_) => (),
-            Err(e) => {
-                eprintln!("ShowWindow failed for {:?}: {:?}", hwnd, e);
-                return BOOL(0);
-            }
+    #[test]
+    fn parses_subcommands() {
+        assert_eq!(args(&[]).unwrap().command, None);
+        assert_eq!(args(&["list"]).unwrap().command, Some(Command::List));
+        assert_eq!(args(&["fix"]).unwrap().command, Some(Command::Fix));
+        assert_eq!(args(&["restore"]).unwrap().command, Some(Command::Restore));
+        assert_eq!(
+            args(&["watch"]).unwrap().command,
+            Some(Command::Watch { interval: 5 })
+        );
+        assert_eq!(
+            args(&["watch", "--interval", "30"]).unwrap().command,
+            Some(Command::Watch { interval: 30 })
+        );
+        assert!(args(&["watch", "--interval", "0"]).is_err());
+        assert!(args(&["--bogus"]).is_err());
+        assert_eq!(args(&["scan"]).unwrap().command, Some(Command::List));
+    }
+
+    #[test]
+    fn global_options_go_anywhere() {
+        let before = args(&["--dry-run", "--strategy", "center", "fix"]).unwrap();
+        let after = args(&["fix", "--dry-run", "--strategy", "center"]).unwrap();
+        for cli in [before, after] {
+            assert!(cli.options.dry_run);
+            assert_eq!(cli.options.strategy, Some(PlacementStrategy::Center));
         }
     }
 
-    println!(
-        "Title: {window_text:?} Percent: {:.2}% {hwnd:?} {rect:?}",
-        display_percent * 100.0
-    );
-
-    match SetWindowPos(
-        hwnd,
-        None,
-        0,
-        0,
-        0,
-        0,
-        SWP_NOZORDER | SWP_NOSIZE | SWP_NOACTIVATE,
-    ) {
-        Ok(_) => BOOL(1),
-        Err(e) => {
-            eprintln!("SetWindowPos failed for {:?}: {:?}", hwnd, e);
-            return BOOL(0);
+    #[test]
+    fn parses_output_options() {
+        let cli = args(&["list", "--format", "tsv", "-q"]).unwrap();
+        assert_eq!(cli.options.format, Format::Tsv);
+        assert!(cli.options.quiet);
+        assert_eq!(args(&[]).unwrap().options.format, Format::Text);
+        assert!(args(&["--format", "json"]).is_err());
+        assert!(args(&["-v", "-q"]).is_err());
+    }
+
+    #[test]
+    fn parses_strategy() {
+        let cli = args(&["--strategy", "nearest-edge"]).unwrap();
+        assert_eq!(cli.options.strategy, Some(PlacementStrategy::NearestEdge));
+        assert!(args(&["--strategy"]).is_err());
+        assert!(args(&["--strategy", "middle"]).is_err());
+    }
+
+    #[test]
+    fn parses_cascade() {
+        let cli = args(&["--layout", "cascade", "--cascade-step", "20"]).unwrap();
+        let resolved = plan_options(&cli, Config::default()).unwrap();
+        assert_eq!(resolved.layout, Layout::Cascade);
+        assert_eq!(resolved.cascade_step, 20);
+
+        let cli = args(&["--cascade-step", "0"]).unwrap();
+        assert!(plan_options(&cli, Config::default()).is_err());
+    }
+
+    #[test]
+    fn parses_fit() {
+        let cli = args(&["--fit", "--min-size", "800x600"]).unwrap();
+        let resolved = plan_options(&cli, Config::default()).unwrap();
+        assert!(resolved.fit);
+        assert!(!resolved.keep_aspect);
+        assert_eq!(resolved.min_size, Size::new(800, 600));
+        assert!(args(&["--min-size", "800"]).is_err());
+    }
+
+    #[test]
+    fn turns_config_switches_off() {
+        let config = || Config::parse("fit = true\ntool-windows = true").unwrap();
+        let cli = args(&["--no-fit", "--no-tool-windows"]).unwrap();
+        let resolved = plan_options(&cli, config()).unwrap();
+        assert!(!resolved.fit);
+        assert!(!resolved.scope.tool_windows);
+
+        let resolved = plan_options(&args(&[]).unwrap(), config()).unwrap();
+        assert!(resolved.fit);
+        assert!(resolved.scope.tool_windows);
+
+        // The last one given wins, on either side of the subcommand
+        let cli = args(&["--fit", "list", "--no-fit"]).unwrap();
+        assert!(!plan_options(&cli, config()).unwrap().fit);
+        let cli = args(&["--no-fit", "list", "--fit"]).unwrap();
+        assert!(plan_options(&cli, Config::default()).unwrap().fit);
+    }
+
+    #[test]
+    fn widens_scope() {
+        let cli = args(&["--tool-windows", "--cloaked-windows"]).unwrap();
+        assert_eq!(
+            plan_options(&cli, Config::default()).unwrap().scope,
+            Scope {
+                tool_windows: true,
+                cloaked_windows: true,
+                ..Scope::default()
+            }
+        );
+        assert_eq!(
+            plan_options(&args(&[]).unwrap(), Config::default())
+                .unwrap()
+                .scope,
+            Scope::default()
+        );
+    }
+
+    #[test]
+    fn parses_move() {
+        let cli = args(&[
+            "move",
+            "--title",
+            "*Notepad",
+            "--process",
+            "notepad.exe",
+            "--force",
+        ])
+        .unwrap();
+        let resolved = plan_options(&cli, Config::default()).unwrap();
+        assert_eq!(
+            resolved.target.unwrap().to_string(),
+            r#"title "*Notepad" process "notepad.exe""#
+        );
+        assert!(resolved.force);
+
+        assert!(args(&["move"]).is_err());
+        assert!(args(&["move", "--force"]).is_err());
+        assert!(args(&["--hwnd", "0x1f"]).is_err());
+        assert!(args(&["--force"]).is_err());
+        let bad_handle = args(&["move", "--hwnd", "window"]).unwrap();
+        assert!(plan_options(&bad_handle, Config::default()).is_err());
+        assert_eq!(
+            plan_options(&args(&[]).unwrap(), Config::default())
+                .unwrap()
+                .target,
+            None
+        );
+    }
+
+    #[test]
+    fn parses_detection() {
+        let cli = args(&["--detection", "reachable"]).unwrap();
+        assert_eq!(
+            plan_options(&cli, Config::default()).unwrap().detection,
+            Detection::Reachable
+        );
+        assert!(args(&["--detection", "caption"]).is_err());
+    }
+
+    #[test]
+    fn command_line_rules_keep_their_order() {
+        let cli = args(&[
+            "fix",
+            "--exclude",
+            "process:mstsc.exe",
+            "--include",
+            "title:*Remote*",
+            "--exclude",
+            "class:TscShell*",
+        ])
+        .unwrap();
+        let rules: Vec<_> = cli.options.rules.iter().map(Rule::to_string).collect();
+        assert_eq!(
+            rules,
+            [
+                r#"exclude process "mstsc.exe""#,
+                r#"include title "*Remote*""#,
+                r#"exclude class "TscShell*""#,
+            ]
+        );
+    }
+
+    #[test]
+    fn command_line_rules_go_around_the_subcommand() {
+        let rules = |argv| -> Vec<_> {
+            let cli = args(argv).unwrap();
+            cli.options.rules.iter().map(Rule::to_string).collect()
+        };
+        assert_eq!(
+            rules(&["--exclude", "title:B", "fix", "--include", "title:A"]),
+            [r#"exclude title "B""#, r#"include title "A""#]
+        );
+        assert_eq!(
+            rules(&[
+                "--include",
+                "title:A",
+                "--exclude",
+                "title:X",
+                "watch",
+                "--include",
+                "title:C",
+            ]),
+            [
+                r#"include title "A""#,
+                r#"exclude title "X""#,
+                r#"include title "C""#,
+            ]
+        );
+    }
+
+    #[test]
+    fn command_line_rules_come_first() {
+        let config =
+            Config::parse("[[rule]]\naction = \"exclude\"\nprocess = \"mstsc.exe\"").unwrap();
+        let cli = args(&["--include", "title:*Important*"]).unwrap();
+
+        let rules: Vec<_> = plan_options(&cli, config)
+            .unwrap()
+            .rules
+            .iter()
+            .map(Rule::to_string)
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                r#"include title "*Important*""#,
+                r#"exclude process "mstsc.exe""#
+            ]
+        );
+        assert!(args(&["--exclude", "mstsc.exe"]).is_err());
+    }
+
+    #[test]
+    fn validates_thresholds() {
+        let cli = args(&["--min-visible", "0.8", "--top-left-bound", "0"]).unwrap();
+        let thresholds = plan_options(&cli, Config::default()).unwrap().thresholds;
+        assert_eq!(thresholds.min_visible, 0.8);
+        assert_eq!(thresholds.top_left_bound, 0);
+
+        for invalid in [
+            &["--min-visible", "1.5"][..],
+            &["--top-left-bound", "-1"],
+            &["--title-bar-pixels", "0"],
+        ] {
+            let cli = args(invalid).unwrap();
+            assert!(
+                plan_options(&cli, Config::default()).is_err(),
+                "{invalid:?}"
+            );
         }
     }
-}
 
-const E_ACCESS_DENIED: HRESULT = HRESULT::from_win32(0x80070005);
-const TOP_LEFT_BOUND: i32 = 100;
+    #[test]
+    fn command_line_strategy_overrides_config() {
+        let config = Config::parse(r#"strategy = "center""#).unwrap();
+        let mut cli = args(&[]).unwrap();
+        assert_eq!(
+            plan_options(&cli, config.clone()).unwrap().strategy,
+            PlacementStrategy::Center
+        );
 
-fn main() {
-    match unsafe { EnumWindows(Some(enum_window_callback), LPARAM(0)) } {
-        Ok(_) => (),
-        Err(e) => match e.code() {
-            E_ACCESS_DENIED => eprintln!("Tip: Try running as administrator."),
-            _ => (),
-        },
+        cli.options.strategy = Some(PlacementStrategy::Cursor);
+        assert_eq!(
+            plan_options(&cli, config).unwrap().strategy,
+            PlacementStrategy::Cursor
+        );
     }
 }
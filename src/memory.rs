@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::Size;
+use crate::plan::PlannedMove;
+use crate::state;
+use crate::window::WindowInfo;
+
+/// Size a window had before a run shrank it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RememberedSize {
+    pub hwnd: isize,
+    /// Checked along with `hwnd`, handles of closed windows are handed out again
+    pub title: String,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Original sizes of shrunk windows, kept in `sizes.toml` between runs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SizeMemory {
+    pub windows: Vec<RememberedSize>,
+}
+
+impl SizeMemory {
+    /// A missing file is an empty memory
+    pub fn load(path: &Path) -> Result<Self> {
+        state::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        state::save(self, path)
+    }
+
+    /// `%APPDATA%\moswb\sizes.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        state::app_path("sizes.toml")
+    }
+
+    pub fn get(&self, window: &WindowInfo) -> Option<Size> {
+        self.windows
+            .iter()
+            .find(|remembered| remembered.hwnd == window.id.0 && remembered.title == window.title)
+            .map(|remembered| Size::new(remembered.width, remembered.height))
+    }
+
+    /// Remember the size of windows that were shrunk and forget those grown back
+    ///
+    /// A window shrunk twice keeps the size it had before the first time. Grid tiles are
+    /// not remembered, growing them back would undo the grid.
+    pub fn update(&mut self, moved: &[PlannedMove]) {
+        for planned in moved {
+            let window = &planned.window;
+            let old = window.restored_rect().size();
+            let new = planned.new_rect.size();
+            match self.get(window) {
+                Some(original) if original.fits_in(new) => {
+                    self.windows
+                        .retain(|remembered| remembered.hwnd != window.id.0);
+                }
+                None if planned.tile.is_none() && !old.fits_in(new) => {
+                    self.windows.push(RememberedSize {
+                        hwnd: window.id.0,
+                        title: window.title.clone(),
+                        width: old.width,
+                        height: old.height,
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::WindowId;
+    use crate::geometry::Rect;
+    use crate::plan::Action;
+
+    fn moved(title: &str, old_rect: Rect, new_rect: Rect) -> PlannedMove {
+        PlannedMove {
+            window: WindowInfo {
+                id: WindowId(42),
+                ..WindowInfo::for_test(title, old_rect)
+            },
+            display_percent: 0.0,
+            old_rect,
+            new_rect,
+            tile: None,
+            actions: vec![Action::Move, Action::Resize],
+        }
+    }
+
+    #[test]
+    fn remembers_until_grown_back() {
+        let mut memory = SizeMemory::default();
+        let shrunk = moved(
+            "Big",
+            Rect::new(3000, 0, 5560, 1440),
+            Rect::new(0, 0, 1366, 728),
+        );
+        memory.update(std::slice::from_ref(&shrunk));
+        assert_eq!(memory.get(&shrunk.window), Some(Size::new(2560, 1440)));
+
+        // Shrinking again keeps the first size
+        let again = moved(
+            "Big",
+            Rect::new(0, 0, 1366, 728),
+            Rect::new(0, 0, 1024, 600),
+        );
+        memory.update(&[again]);
+        assert_eq!(memory.get(&shrunk.window), Some(Size::new(2560, 1440)));
+
+        let grown = moved(
+            "Big",
+            Rect::new(0, 0, 1024, 600),
+            Rect::new(0, 0, 2560, 1440),
+        );
+        memory.update(&[grown]);
+        assert_eq!(memory, SizeMemory::default());
+    }
+
+    #[test]
+    fn ignores_reused_handles() {
+        let mut memory = SizeMemory::default();
+        memory.update(&[moved(
+            "Big",
+            Rect::new(3000, 0, 5560, 1440),
+            Rect::new(0, 0, 1366, 728),
+        )]);
+
+        let other = moved(
+            "Other",
+            Rect::new(0, 0, 800, 600),
+            Rect::new(0, 0, 800, 600),
+        );
+        assert_eq!(memory.get(&other.window), None);
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let memory = SizeMemory {
+            windows: vec![RememberedSize {
+                hwnd: 42,
+                title: "Big".to_string(),
+                width: 2560,
+                height: 1440,
+            }],
+        };
+        let path = std::env::temp_dir()
+            .join("moswb-sizes-test")
+            .join("sizes.toml");
+
+        memory.save(&path).unwrap();
+        assert_eq!(SizeMemory::load(&path).unwrap(), memory);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(SizeMemory::load(&path).unwrap(), SizeMemory::default());
+    }
+}
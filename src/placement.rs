@@ -0,0 +1,312 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use serde::Deserialize;
+
+use crate::backend::Monitor;
+use crate::geometry::{Rect, Size};
+
+/// How the destination of a rescued window is chosen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum PlacementStrategy {
+    /// Top-left corner of the primary work area
+    #[default]
+    TopLeft,
+    /// Centered on the primary work area
+    Center,
+    /// The smallest move that brings the window fully onto some work area
+    NearestEdge,
+    /// Centered on the monitor under the mouse cursor
+    Cursor,
+    /// The offset the window had on its lost monitor, applied to the monitor with this index
+    ///
+    /// The primary monitor stands in for an index past the last monitor.
+    Relative(usize),
+}
+
+impl FromStr for PlacementStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "top-left" => PlacementStrategy::TopLeft,
+            "center" => PlacementStrategy::Center,
+            "nearest-edge" => PlacementStrategy::NearestEdge,
+            "cursor" => PlacementStrategy::Cursor,
+            _ => match s.strip_prefix("relative:") {
+                Some(monitor) => PlacementStrategy::Relative(
+                    monitor
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid monitor index {monitor:?}"))?,
+                ),
+                None => bail!(
+                    "Unknown placement strategy {s:?}, \
+                     expected top-left, center, nearest-edge, cursor or relative:<monitor>"
+                ),
+            },
+        })
+    }
+}
+
+impl TryFrom<String> for PlacementStrategy {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for PlacementStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementStrategy::TopLeft => write!(f, "top-left"),
+            PlacementStrategy::Center => write!(f, "center"),
+            PlacementStrategy::NearestEdge => write!(f, "nearest-edge"),
+            PlacementStrategy::Cursor => write!(f, "cursor"),
+            PlacementStrategy::Relative(monitor) => write!(f, "relative:{monitor}"),
+        }
+    }
+}
+
+/// The primary monitor, or the first one if none is flagged
+pub fn primary_monitor(monitors: &[Monitor]) -> Option<&Monitor> {
+    monitors
+        .iter()
+        .find(|monitor| monitor.primary)
+        .or(monitors.first())
+}
+
+/// Work area of the monitor containing a point, falling back to the primary
+pub fn work_area_at(monitors: &[Monitor], x: i32, y: i32) -> Rect {
+    monitors
+        .iter()
+        .find(|monitor| monitor.work_area.contains_point(x, y))
+        .or(primary_monitor(monitors))
+        .map_or(Rect::default(), |monitor| monitor.work_area)
+}
+
+/// Slide `rect` the least distance that puts it inside `area`, top-left wins if it is too big
+pub fn fit_within(rect: Rect, area: Rect) -> Rect {
+    let left = rect.left.min(area.right - rect.width()).max(area.left);
+    let top = rect.top.min(area.bottom - rect.height()).max(area.top);
+    rect.moved_to(left, top)
+}
+
+/// Center `rect` on `area`, keeping the top-left corner inside it
+pub fn center_in(rect: Rect, area: Rect) -> Rect {
+    let left = area.left + ((area.width() - rect.width()) / 2).max(0);
+    let top = area.top + ((area.height() - rect.height()) / 2).max(0);
+    rect.moved_to(left, top)
+}
+
+/// Shrink `rect` until it fits `area`, but not below `min_size`, then slide it inside
+///
+/// With `keep_aspect` both sides are scaled by the same factor. A window that already fits
+/// keeps its size.
+pub fn shrink_to_fit(rect: Rect, area: Rect, min_size: Size, keep_aspect: bool) -> Rect {
+    let size = rect.size();
+    if size.fits_in(area.size()) {
+        return fit_within(rect, area);
+    }
+
+    let mut fitted = if keep_aspect {
+        let scale = (area.width() as f64 / size.width as f64)
+            .min(area.height() as f64 / size.height as f64);
+        Size::new(
+            (size.width as f64 * scale) as i32,
+            (size.height as f64 * scale) as i32,
+        )
+    } else {
+        Size::new(size.width.min(area.width()), size.height.min(area.height()))
+    };
+    fitted.width = fitted.width.max(min_size.width.min(size.width));
+    fitted.height = fitted.height.max(min_size.height.min(size.height));
+    fit_within(rect.resized(fitted), area)
+}
+
+impl PlacementStrategy {
+    /// Destination of a window currently at `rect`
+    pub fn place(&self, rect: Rect, monitors: &[Monitor], cursor: Option<(i32, i32)>) -> Rect {
+        let Some(primary) = primary_monitor(monitors) else {
+            return rect.moved_to(0, 0);
+        };
+
+        match *self {
+            PlacementStrategy::TopLeft => {
+                rect.moved_to(primary.work_area.left, primary.work_area.top)
+            }
+            PlacementStrategy::Center => center_in(rect, primary.work_area),
+            PlacementStrategy::NearestEdge => monitors
+                .iter()
+                .map(|monitor| fit_within(rect, monitor.work_area))
+                .min_by_key(|placed| {
+                    let dx = (placed.left - rect.left) as i64;
+                    let dy = (placed.top - rect.top) as i64;
+                    dx * dx + dy * dy
+                })
+                .unwrap_or(rect),
+            PlacementStrategy::Cursor => {
+                let monitor = cursor
+                    .and_then(|(x, y)| {
+                        monitors
+                            .iter()
+                            .find(|monitor| monitor.rect.contains_point(x, y))
+                    })
+                    .unwrap_or(primary);
+                center_in(rect, monitor.work_area)
+            }
+            PlacementStrategy::Relative(index) => {
+                // Treat the lost monitor as if it had the size of the chosen one
+                let area = monitors.get(index).unwrap_or(primary).work_area;
+                let left = area.left + (rect.left - area.left).rem_euclid(area.width().max(1));
+                let top = area.top + (rect.top - area.top).rem_euclid(area.height().max(1));
+                fit_within(rect.moved_to(left, top), area)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(rect: Rect, work_area: Rect, primary: bool) -> Monitor {
+        Monitor {
+            rect,
+            work_area,
+            primary,
+        }
+    }
+
+    /// Laptop with a bottom taskbar, external monitor on its left with a left taskbar
+    fn dual() -> Vec<Monitor> {
+        vec![
+            monitor(
+                Rect::new(-2560, -360, 0, 1080),
+                Rect::new(-2500, -360, 0, 1080),
+                false,
+            ),
+            monitor(
+                Rect::new(0, 0, 1920, 1080),
+                Rect::new(0, 0, 1920, 1040),
+                true,
+            ),
+        ]
+    }
+
+    const WINDOW: Rect = Rect::new(2500, 300, 3300, 900);
+
+    #[test]
+    fn parses_strategy_names() {
+        for name in ["top-left", "center", "nearest-edge", "cursor", "relative:1"] {
+            let strategy: PlacementStrategy = name.parse().unwrap();
+            assert_eq!(strategy.to_string(), name);
+        }
+        assert!("middle".parse::<PlacementStrategy>().is_err());
+        assert!("relative:x".parse::<PlacementStrategy>().is_err());
+    }
+
+    #[test]
+    fn top_left_of_primary_work_area() {
+        let placed = PlacementStrategy::TopLeft.place(WINDOW, &dual(), None);
+        assert_eq!(placed, Rect::new(0, 0, 800, 600));
+    }
+
+    #[test]
+    fn centered_on_primary() {
+        let placed = PlacementStrategy::Center.place(WINDOW, &dual(), None);
+        assert_eq!(placed, Rect::new(560, 220, 1360, 820));
+    }
+
+    #[test]
+    fn centered_window_larger_than_work_area() {
+        let big = Rect::new(3000, 0, 5560, 1440);
+        let placed = PlacementStrategy::Center.place(big, &dual(), None);
+        assert_eq!((placed.left, placed.top), (0, 0));
+    }
+
+    #[test]
+    fn nearest_edge_moves_the_least() {
+        let placed = PlacementStrategy::NearestEdge.place(WINDOW, &dual(), None);
+        assert_eq!(placed, Rect::new(1120, 300, 1920, 900));
+
+        let above_left = Rect::new(-1500, -900, -700, -300);
+        let placed = PlacementStrategy::NearestEdge.place(above_left, &dual(), None);
+        assert_eq!(placed, Rect::new(-1500, -360, -700, 240));
+    }
+
+    #[test]
+    fn cursor_monitor() {
+        let placed = PlacementStrategy::Cursor.place(WINDOW, &dual(), Some((-100, 500)));
+        assert_eq!(placed, Rect::new(-1650, 60, -850, 660));
+    }
+
+    #[test]
+    fn cursor_falls_back_to_primary() {
+        let placed = PlacementStrategy::Cursor.place(WINDOW, &dual(), None);
+        assert_eq!(
+            placed,
+            PlacementStrategy::Center.place(WINDOW, &dual(), None)
+        );
+    }
+
+    #[test]
+    fn relative_position_on_chosen_monitor() {
+        let placed = PlacementStrategy::Relative(1).place(WINDOW, &dual(), None);
+        assert_eq!(placed, Rect::new(580, 300, 1380, 900));
+
+        let placed = PlacementStrategy::Relative(0).place(WINDOW, &dual(), None);
+        assert_eq!(placed, Rect::new(-2500, 300, -1700, 900));
+    }
+
+    #[test]
+    fn relative_position_stays_inside_work_area() {
+        let near_edge = Rect::new(3700, 300, 4500, 900);
+        let placed = PlacementStrategy::Relative(1).place(near_edge, &dual(), None);
+        assert_eq!(placed, Rect::new(1120, 300, 1920, 900));
+    }
+
+    #[test]
+    fn shrinks_to_laptop_work_area() {
+        let laptop = Rect::new(0, 0, 1366, 728);
+        let big = Rect::new(0, 0, 2560, 1440);
+
+        let fitted = shrink_to_fit(big, laptop, Size::new(320, 240), false);
+        assert_eq!(fitted, laptop);
+
+        let fitted = shrink_to_fit(big, laptop, Size::new(320, 240), true);
+        assert_eq!(fitted, Rect::new(0, 0, 1294, 728));
+    }
+
+    #[test]
+    fn shrinking_stops_at_min_size() {
+        let tiny = Rect::new(100, 100, 500, 400);
+        let big = Rect::new(0, 0, 2560, 1440);
+
+        let fitted = shrink_to_fit(big, tiny, Size::new(640, 480), true);
+
+        assert_eq!(fitted.size(), Size::new(640, 480));
+    }
+
+    #[test]
+    fn fitting_window_keeps_its_size() {
+        let placed = Rect::new(1500, 600, 2300, 1200);
+
+        let fitted = shrink_to_fit(
+            placed,
+            Rect::new(0, 0, 1920, 1040),
+            Size::new(320, 240),
+            true,
+        );
+
+        assert_eq!(fitted, Rect::new(1120, 440, 1920, 1040));
+    }
+
+    #[test]
+    fn without_monitors_goes_to_origin() {
+        let placed = PlacementStrategy::Center.place(WINDOW, &[], None);
+        assert_eq!(placed, Rect::new(0, 0, 800, 600));
+    }
+}
@@ -0,0 +1,841 @@
+use crate::backend::WindowSystem;
+use crate::error::{Error, WindowContext};
+use crate::geometry::{get_display_percent, Rect, Size};
+use crate::layout::{cascade, free_space, grid, Layout, Tile, DEFAULT_CASCADE_STEP};
+use crate::memory::SizeMemory;
+use crate::placement::{center_in, fit_within, shrink_to_fit, work_area_at, PlacementStrategy};
+use crate::report::{Report, SkipReason, Skipped};
+use crate::rescue::{
+    classify_parking, is_reachable, monitor_rects, on_screen_reason, thresholds_for, Detection,
+    ThresholdOverride, Thresholds,
+};
+use crate::rules::{rule_skip_reason, Rule, Target};
+use crate::window::{Scope, Snapshot, WindowInfo, WindowKind};
+
+/// A single step taken on a window, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Rewrite the restore rect of a minimized window, which stays minimized
+    SetNormalRect,
+    /// Restore from maximized to the normal rect
+    Restore,
+    /// Move to the top-left corner of the new rect without resizing
+    Move,
+    /// Resize to the size of the new rect without moving
+    Resize,
+    /// Maximize again on the monitor the window was moved to
+    Maximize,
+}
+
+/// Smallest size `--fit` shrinks a window to
+pub const DEFAULT_MIN_SIZE: Size = Size::new(320, 240);
+
+/// What will happen to one off-screen window
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedMove {
+    pub window: WindowInfo,
+    pub display_percent: f32,
+    pub old_rect: Rect,
+    pub new_rect: Rect,
+    /// Cell assigned by the grid layout
+    pub tile: Option<Tile>,
+    pub actions: Vec<Action>,
+}
+
+/// Every window move decided from a snapshot, before anything is touched
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RelocationPlan {
+    pub moves: Vec<PlannedMove>,
+    pub skipped: Vec<Skipped>,
+    /// Carried over from the snapshot
+    pub failures: Vec<Error>,
+}
+
+/// Rect deciding whether a window is lost, minimized windows are judged by where they restore to
+fn judged_rect(window: &WindowInfo) -> Rect {
+    if window.minimized {
+        window.normal_rect
+    } else {
+        window.rect
+    }
+}
+
+/// Whether a window moves along with its owner instead of being placed on its own
+///
+/// Only lost windows follow, windows on screen, hidden from the user, minimized or ruled
+/// out stay where they are.
+fn follows_owner(
+    snapshot: &Snapshot,
+    reasons: &[Option<SkipReason>],
+    not_lost: &[Option<SkipReason>],
+    index: usize,
+) -> bool {
+    let Some(owner) = snapshot.windows[index]
+        .owner
+        .and_then(|id| snapshot.position(id))
+    else {
+        return false;
+    };
+    let eligible = not_lost[index].is_none()
+        && reasons[index].is_none_or(|reason| {
+            matches!(
+                reason,
+                SkipReason::NotTargeted
+                    | SkipReason::OutOfScope(WindowKind::Tool | WindowKind::Owned)
+            )
+        });
+    eligible && (reasons[owner].is_none() || follows_owner(snapshot, reasons, not_lost, owner))
+}
+
+/// Actions that bring a window to its new rect before any resize
+pub(crate) fn initial_actions(window: &WindowInfo) -> Vec<Action> {
+    if window.minimized {
+        vec![Action::SetNormalRect]
+    } else if window.maximized {
+        vec![Action::Restore, Action::Move]
+    } else {
+        vec![Action::Move]
+    }
+}
+
+/// Resize and maximize again once the new rect is final
+pub(crate) fn final_actions(planned: &mut PlannedMove) {
+    // The restore rect of a minimized window carries its size
+    if planned.window.minimized {
+        return;
+    }
+    if planned.new_rect.size() != planned.window.restored_rect().size() {
+        planned.actions.push(Action::Resize);
+    }
+    if planned.window.maximized {
+        planned.actions.push(Action::Maximize);
+    }
+}
+
+/// Why a window is not lost, if it is not
+fn not_lost_reason(
+    window: &WindowInfo,
+    monitors: &[Rect],
+    work_areas: &[Rect],
+    display_percent: f32,
+    options: &PlanOptions,
+) -> Option<SkipReason> {
+    let rect = judged_rect(window);
+    classify_parking(rect, monitors)
+        .map(SkipReason::Parked)
+        .or_else(|| match options.detection {
+            Detection::Area => {
+                let thresholds = thresholds_for(
+                    window,
+                    rect,
+                    monitors,
+                    &options.thresholds,
+                    &options.overrides,
+                );
+                on_screen_reason(rect, monitors, display_percent, &thresholds)
+            }
+            Detection::Reachable => {
+                is_reachable(rect, work_areas).then_some(SkipReason::TitleBarReachable)
+            }
+        })
+}
+
+/// Why a window should stay where it is, if it should, given why it is not lost
+fn skip_reason(window: &WindowInfo, not_lost: Option<SkipReason>) -> Option<SkipReason> {
+    if window.minimized {
+        not_lost.is_some().then_some(SkipReason::Minimized)
+    } else {
+        not_lost
+    }
+}
+
+/// Knobs of the planner
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanOptions {
+    pub strategy: PlacementStrategy,
+    pub layout: Layout,
+    /// Pixels between cascaded windows, both down and right
+    pub cascade_step: i32,
+    /// Shrink windows larger than the work area they are moved to
+    pub fit: bool,
+    /// Floor for `fit`
+    pub min_size: Size,
+    /// Scale both sides by the same factor when fitting
+    pub keep_aspect: bool,
+    pub detection: Detection,
+    /// Used by [`Detection::Area`]
+    pub thresholds: Thresholds,
+    /// Applied in order on top of `thresholds` to the windows they match
+    pub overrides: Vec<ThresholdOverride>,
+    /// Include and exclude rules, the first matching one decides
+    pub rules: Vec<Rule>,
+    /// Kinds of windows rescued besides app windows
+    pub scope: Scope,
+    /// Only move these windows, whatever the scope and rules say
+    pub target: Option<Target>,
+    /// Move targeted windows even when they are not lost
+    pub force: bool,
+}
+
+impl Default for PlanOptions {
+    fn default() -> Self {
+        Self {
+            strategy: PlacementStrategy::default(),
+            layout: Layout::default(),
+            cascade_step: DEFAULT_CASCADE_STEP,
+            fit: false,
+            min_size: DEFAULT_MIN_SIZE,
+            keep_aspect: false,
+            detection: Detection::default(),
+            thresholds: Thresholds::default(),
+            overrides: Vec::new(),
+            rules: Vec::new(),
+            scope: Scope::default(),
+            target: None,
+            force: false,
+        }
+    }
+}
+
+impl RelocationPlan {
+    /// Decide which windows of a snapshot need to be moved back and where to
+    pub fn new(snapshot: &Snapshot, options: &PlanOptions) -> Self {
+        let monitors = monitor_rects(&snapshot.monitors);
+        let work_areas: Vec<_> = snapshot
+            .monitors
+            .iter()
+            .map(|monitor| monitor.work_area)
+            .collect();
+        let mut plan = Self {
+            skipped: snapshot.skipped.clone(),
+            failures: snapshot.failures.clone(),
+            ..Self::default()
+        };
+
+        let display_percents: Vec<_> = snapshot
+            .windows
+            .iter()
+            .map(|window| get_display_percent(judged_rect(window), &monitors))
+            .collect();
+        let not_lost: Vec<_> = snapshot
+            .windows
+            .iter()
+            .zip(&display_percents)
+            .map(|(window, &display_percent)| {
+                not_lost_reason(window, &monitors, &work_areas, display_percent, options)
+            })
+            .collect();
+        let mut reasons: Vec<_> = snapshot
+            .windows
+            .iter()
+            .zip(&not_lost)
+            .map(|(window, &not_lost)| {
+                let targeted = options.target.as_ref().map(|target| target.matches(window));
+                if targeted == Some(true) {
+                    if options.force {
+                        return None;
+                    }
+                    return skip_reason(window, not_lost);
+                }
+
+                // Dialogs are handled through their owner whatever the scope
+                let has_owner = window.owner.and_then(|id| snapshot.position(id)).is_some();
+                let in_scope = options.scope.allows(window.kind)
+                    || (window.kind == WindowKind::Owned && has_owner);
+                if !in_scope {
+                    return Some(SkipReason::OutOfScope(window.kind));
+                }
+                let reason = rule_skip_reason(&options.rules, window)
+                    .or_else(|| skip_reason(window, not_lost));
+                // Windows that are not targeted still count as on screen or lost
+                match (reason, targeted) {
+                    (None, Some(false)) => Some(SkipReason::NotTargeted),
+                    _ => reason,
+                }
+            })
+            .collect();
+        // Dialogs of an owner left alone for another reason than being on screen stay too,
+        // and so do their own dialogs. A dialog asked for by name is moved on its own.
+        let mut changed = options.target.is_none();
+        while changed {
+            changed = false;
+            for (index, window) in snapshot.windows.iter().enumerate() {
+                let owner = window.owner.and_then(|id| snapshot.position(id));
+                let owner_skipped = owner
+                    .and_then(|owner| reasons[owner])
+                    .is_some_and(|reason| !reason.is_on_screen());
+                if window.kind == WindowKind::Owned && reasons[index].is_none() && owner_skipped {
+                    reasons[index] = Some(SkipReason::OwnerSkipped);
+                    changed = true;
+                }
+            }
+        }
+        let followers: Vec<_> = (0..snapshot.windows.len())
+            .map(|index| follows_owner(snapshot, &reasons, &not_lost, index))
+            .collect();
+
+        // Dialogs centered over an owner that stays, kept out of the layout
+        let mut centered = Vec::new();
+        for (index, window) in snapshot.windows.iter().enumerate() {
+            if followers[index] {
+                continue;
+            }
+            if let Some(reason) = reasons[index] {
+                plan.skipped.push(Skipped {
+                    window: window.clone(),
+                    reason,
+                });
+                continue;
+            }
+
+            let owner = window.owner.and_then(|id| snapshot.position(id));
+            let new_rect = match owner {
+                Some(owner)
+                    if window.kind == WindowKind::Owned
+                        && reasons[owner].is_some_and(|reason| reason.is_on_screen()) =>
+                {
+                    centered.push(PlannedMove {
+                        window: window.clone(),
+                        display_percent: display_percents[index],
+                        old_rect: window.rect,
+                        // Where the owner is shown, the normal rect of a maximized owner
+                        // can be anywhere
+                        new_rect: center_in(window.restored_rect(), snapshot.windows[owner].rect),
+                        tile: None,
+                        actions: initial_actions(window),
+                    });
+                    continue;
+                }
+                _ => {
+                    let rect = options.strategy.place(
+                        window.restored_rect(),
+                        &snapshot.monitors,
+                        snapshot.cursor,
+                    );
+                    if options.fit {
+                        let area = work_area_at(&snapshot.monitors, rect.left, rect.top);
+                        shrink_to_fit(rect, area, options.min_size, options.keep_aspect)
+                    } else {
+                        rect
+                    }
+                }
+            };
+
+            plan.moves.push(PlannedMove {
+                window: window.clone(),
+                display_percent: display_percents[index],
+                old_rect: window.rect,
+                new_rect,
+                tile: None,
+                actions: initial_actions(window),
+            });
+        }
+        if let PlacementStrategy::Relative(index) = options.strategy {
+            let count = snapshot.monitors.len();
+            if index >= count && !plan.moves.is_empty() {
+                plan.failures.push(Error::NoSuchMonitor { index, count });
+            }
+        }
+
+        // Windows staying where they are are what a rescued window should not cover,
+        // whatever they were skipped for, as long as they can be seen
+        let occupied: Vec<_> = plan
+            .skipped
+            .iter()
+            .map(|skipped| &skipped.window)
+            .filter(|window| !window.minimized && window.kind != WindowKind::Cloaked)
+            .map(|window| window.rect)
+            .filter(|rect| {
+                snapshot
+                    .monitors
+                    .iter()
+                    .any(|monitor| rect.intersection(&monitor.rect).is_some())
+            })
+            .collect();
+        arrange(&mut plan.moves, snapshot, &occupied, options);
+        plan.moves.extend(centered);
+
+        // Owned windows keep their offset from their owner, planned windows grow while
+        // walking them so owned windows of owned windows follow too
+        let mut next = 0;
+        while let Some(owner) = plan.moves.get(next) {
+            let origin = owner.window.restored_rect();
+            let (dx, dy) = (
+                owner.new_rect.left - origin.left,
+                owner.new_rect.top - origin.top,
+            );
+            let id = owner.window.id;
+            for (index, window) in snapshot.windows.iter().enumerate() {
+                if followers[index] && window.owner == Some(id) {
+                    plan.moves.push(PlannedMove {
+                        window: window.clone(),
+                        display_percent: display_percents[index],
+                        old_rect: window.rect,
+                        new_rect: window.restored_rect().offset(dx, dy),
+                        tile: None,
+                        actions: initial_actions(window),
+                    });
+                }
+            }
+            next += 1;
+        }
+
+        for planned in &mut plan.moves {
+            final_actions(planned);
+        }
+        plan
+    }
+
+    /// Grow windows shrunk by an earlier run back to their remembered size
+    ///
+    /// Only windows left where they are because they are on screen, and whose work area is
+    /// now large enough, are planned. They are taken out of `skipped`, windows skipped for
+    /// any other reason are not touched.
+    pub fn grow_back(&mut self, snapshot: &Snapshot, memory: &SizeMemory) {
+        let skipped = std::mem::take(&mut self.skipped);
+        for skipped in skipped {
+            let window = &skipped.window;
+            let area = work_area_at(&snapshot.monitors, window.rect.left, window.rect.top);
+            let size = match memory.get(window) {
+                Some(size)
+                    if skipped.reason.is_on_screen()
+                        && !window.minimized
+                        && !window.maximized
+                        && size != window.rect.size()
+                        && size.fits_in(area.size()) =>
+                {
+                    size
+                }
+                _ => {
+                    self.skipped.push(skipped);
+                    continue;
+                }
+            };
+
+            let new_rect = fit_within(window.rect.resized(size), area);
+            let mut actions = Vec::new();
+            if (new_rect.left, new_rect.top) != (window.rect.left, window.rect.top) {
+                actions.push(Action::Move);
+            }
+            actions.push(Action::Resize);
+            self.moves.push(PlannedMove {
+                window: window.clone(),
+                display_percent: get_display_percent(
+                    window.rect,
+                    &monitor_rects(&snapshot.monitors),
+                ),
+                old_rect: window.rect,
+                new_rect,
+                tile: None,
+                actions,
+            });
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+/// Apply the multi-window layout on top of the per-window placement
+fn arrange(
+    moves: &mut [PlannedMove],
+    snapshot: &Snapshot,
+    occupied: &[Rect],
+    options: &PlanOptions,
+) {
+    let mut targets: Vec<_> = moves.iter().map(|planned| planned.new_rect).collect();
+    match options.layout {
+        Layout::Stack => return,
+        Layout::Cascade => cascade(&mut targets, &snapshot.monitors, options.cascade_step),
+        Layout::FreeSpace => free_space(
+            &mut targets,
+            &snapshot.monitors,
+            occupied,
+            options.cascade_step,
+        ),
+        Layout::Grid => {
+            // The first window's placement picks the monitor the grid goes on
+            let Some(first) = targets.first() else {
+                return;
+            };
+            let area = work_area_at(&snapshot.monitors, first.left, first.top);
+            let tiles = grid(&mut targets, area);
+            for (planned, tile) in moves.iter_mut().zip(tiles) {
+                planned.tile = Some(tile);
+            }
+        }
+    }
+
+    for (planned, target) in moves.iter_mut().zip(targets) {
+        planned.new_rect = target;
+    }
+}
+
+/// Carry out the actions of a single planned move
+pub fn apply_move(system: &mut impl WindowSystem, planned: &PlannedMove) -> Result<(), Error> {
+    let id = planned.window.id;
+    let context = || WindowContext::new(id, Some(&planned.window.title));
+    if system.is_hung(id) {
+        return Err(Error::HungWindow { window: context() });
+    }
+
+    for action in &planned.actions {
+        match action {
+            Action::SetNormalRect => system.set_normal_rect(id, planned.new_rect).map_err(|e| {
+                Error::from_backend(context(), "SetWindowPlacement", e, |window, message| {
+                    Error::Move { window, message }
+                })
+            })?,
+            Action::Restore => system.restore(id).map_err(|e| {
+                Error::from_backend(context(), "ShowWindow", e, |window, message| {
+                    Error::Restore { window, message }
+                })
+            })?,
+            Action::Move => system
+                .move_to(id, planned.new_rect.left, planned.new_rect.top)
+                .map_err(|e| {
+                    Error::from_backend(context(), "SetWindowPos", e, |window, message| {
+                        Error::Move { window, message }
+                    })
+                })?,
+            Action::Resize => system
+                .resize(id, planned.new_rect.width(), planned.new_rect.height())
+                .map_err(|e| {
+                    Error::from_backend(context(), "SetWindowPos", e, |window, message| {
+                        Error::Move { window, message }
+                    })
+                })?,
+            Action::Maximize => system.maximize(id).map_err(|e| {
+                Error::from_backend(context(), "ShowWindow", e, |window, message| {
+                    Error::Maximize { window, message }
+                })
+            })?,
+        }
+    }
+    Ok(())
+}
+
+/// Carry out a whole plan, continuing past windows that fail
+pub fn apply(system: &mut impl WindowSystem, plan: &RelocationPlan) -> Report {
+    let mut report = Report::new(plan);
+    for planned in &plan.moves {
+        report.record(planned, apply_move(system, planned));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Monitor;
+    use crate::memory::RememberedSize;
+    use crate::rules::RuleAction;
+    use crate::window::WindowKind;
+
+    fn snapshot(windows: Vec<WindowInfo>) -> Snapshot {
+        Snapshot {
+            monitors: vec![Monitor {
+                rect: Rect::new(0, 0, 1920, 1080),
+                work_area: Rect::new(0, 0, 1920, 1080),
+                primary: true,
+            }],
+            cursor: None,
+            windows,
+            skipped: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn plans_move_to_origin() {
+        let lost = WindowInfo::for_test("Lost", Rect::new(-900, 300, -100, 900));
+        let plan = RelocationPlan::new(&snapshot(vec![lost.clone()]), &PlanOptions::default());
+
+        assert_eq!(
+            plan.moves,
+            [PlannedMove {
+                window: lost,
+                display_percent: 0.0,
+                old_rect: Rect::new(-900, 300, -100, 900),
+                new_rect: Rect::new(0, 0, 800, 600),
+                tile: None,
+                actions: vec![Action::Move],
+            }]
+        );
+    }
+
+    #[test]
+    fn plans_move_into_primary_work_area() {
+        let secondary = Monitor {
+            rect: Rect::new(-1920, 0, 0, 1080),
+            work_area: Rect::new(-1920, 0, 0, 1040),
+            primary: false,
+        };
+        let primary = Monitor {
+            rect: Rect::new(0, 0, 1920, 1080),
+            work_area: Rect::new(60, 0, 1920, 1080),
+            primary: true,
+        };
+        let mut snapshot = snapshot(vec![WindowInfo::for_test(
+            "Lost",
+            Rect::new(-900, 1300, -100, 1900),
+        )]);
+        snapshot.monitors = vec![secondary, primary];
+
+        let plan = RelocationPlan::new(&snapshot, &PlanOptions::default());
+
+        assert_eq!(plan.moves[0].new_rect, Rect::new(60, 0, 860, 600));
+    }
+
+    #[test]
+    fn plans_with_selected_strategy() {
+        let options = PlanOptions {
+            strategy: PlacementStrategy::Center,
+            ..PlanOptions::default()
+        };
+        let snapshot = snapshot(vec![WindowInfo::for_test(
+            "Lost",
+            Rect::new(-900, 300, -100, 900),
+        )]);
+
+        let plan = RelocationPlan::new(&snapshot, &options);
+
+        assert_eq!(plan.moves[0].new_rect, Rect::new(560, 240, 1360, 840));
+    }
+
+    #[test]
+    fn plans_cascade_in_window_order() {
+        let options = PlanOptions {
+            layout: Layout::Cascade,
+            cascade_step: 40,
+            ..PlanOptions::default()
+        };
+        let snapshot = snapshot(vec![
+            WindowInfo::for_test("First", Rect::new(-900, 300, -100, 900)),
+            WindowInfo::for_test("Second", Rect::new(2500, 300, 3100, 700)),
+            WindowInfo::for_test("Third", Rect::new(-900, -900, -100, -300)),
+        ]);
+
+        let plan = RelocationPlan::new(&snapshot, &options);
+
+        let targets: Vec<_> = plan.moves.iter().map(|planned| planned.new_rect).collect();
+        assert_eq!(
+            targets,
+            [
+                Rect::new(0, 0, 800, 600),
+                Rect::new(40, 40, 640, 440),
+                Rect::new(80, 80, 880, 680),
+            ]
+        );
+    }
+
+    #[test]
+    fn plans_grid_with_tiles_and_resizes() {
+        let options = PlanOptions {
+            layout: Layout::Grid,
+            ..PlanOptions::default()
+        };
+        let snapshot = snapshot(vec![
+            WindowInfo::for_test("Small", Rect::new(-900, 300, -500, 600)),
+            WindowInfo::for_test("Large", Rect::new(2500, 0, 4420, 1080)),
+        ]);
+
+        let plan = RelocationPlan::new(&snapshot, &options);
+
+        assert_eq!(plan.moves[0].new_rect, Rect::new(0, 0, 400, 300));
+        assert_eq!(plan.moves[0].actions, [Action::Move]);
+        assert_eq!(plan.moves[1].new_rect, Rect::new(960, 0, 1920, 540));
+        assert_eq!(plan.moves[1].actions, [Action::Move, Action::Resize]);
+        assert_eq!(plan.moves[1].tile, Some(Tile { row: 0, column: 1 }));
+    }
+
+    #[test]
+    fn plans_free_space_beside_on_screen_windows() {
+        let options = PlanOptions {
+            layout: Layout::FreeSpace,
+            ..PlanOptions::default()
+        };
+        let mut minimized = WindowInfo::for_test("Minimized", Rect::new(0, 0, 1920, 1080));
+        minimized.minimized = true;
+        let snapshot = snapshot(vec![
+            WindowInfo::for_test("Editor", Rect::new(0, 0, 1000, 1080)),
+            minimized,
+            WindowInfo::for_test("Lost", Rect::new(-900, 300, -100, 900)),
+        ]);
+
+        let plan = RelocationPlan::new(&snapshot, &options);
+
+        assert_eq!(plan.moves[0].new_rect, Rect::new(1000, 0, 1800, 600));
+    }
+
+    #[test]
+    fn plans_free_space_beside_excluded_windows() {
+        let options = PlanOptions {
+            layout: Layout::FreeSpace,
+            rules: vec![Rule::parse(RuleAction::Exclude, "title:Editor").unwrap()],
+            ..PlanOptions::default()
+        };
+        let mut palette = WindowInfo::for_test("Palette", Rect::new(1000, 0, 1920, 400));
+        palette.kind = WindowKind::Tool;
+        let snapshot = snapshot(vec![
+            WindowInfo::for_test("Editor", Rect::new(0, 0, 1000, 1080)),
+            palette,
+            WindowInfo::for_test("Lost", Rect::new(-900, 300, -100, 900)),
+        ]);
+
+        let plan = RelocationPlan::new(&snapshot, &options);
+
+        assert_eq!(plan.skipped[0].reason, SkipReason::Excluded(0));
+        assert_eq!(plan.moves[0].new_rect, Rect::new(1000, 400, 1800, 1000));
+    }
+
+    #[test]
+    fn plans_fit_to_work_area() {
+        let options = PlanOptions {
+            fit: true,
+            keep_aspect: true,
+            ..PlanOptions::default()
+        };
+        let snapshot = snapshot(vec![WindowInfo::for_test(
+            "Big",
+            Rect::new(3000, 0, 5560, 1440),
+        )]);
+
+        let plan = RelocationPlan::new(&snapshot, &options);
+
+        assert_eq!(plan.moves[0].new_rect, Rect::new(0, 0, 1920, 1080));
+        assert_eq!(plan.moves[0].actions, [Action::Move, Action::Resize]);
+    }
+
+    #[test]
+    fn plans_unreachable_window_in_reachable_mode() {
+        let options = PlanOptions {
+            detection: Detection::Reachable,
+            ..PlanOptions::default()
+        };
+        let mut snapshot = snapshot(vec![
+            WindowInfo::for_test("Caption above", Rect::new(200, -100, 1000, 700)),
+            WindowInfo::for_test("Mostly off", Rect::new(1700, 300, 2700, 1000)),
+        ]);
+        snapshot.monitors[0].work_area = Rect::new(0, 40, 1920, 1080);
+
+        let plan = RelocationPlan::new(&snapshot, &options);
+
+        assert_eq!(plan.moves.len(), 1);
+        assert_eq!(plan.moves[0].window.title, "Caption above");
+        assert_eq!(plan.skipped[0].reason, SkipReason::TitleBarReachable);
+
+        let plan = RelocationPlan::new(&snapshot, &PlanOptions::default());
+        assert_eq!(plan.moves[0].window.title, "Mostly off");
+    }
+
+    #[test]
+    fn grows_back_only_windows_on_screen() {
+        let options = PlanOptions {
+            fit: true,
+            rules: vec![Rule::parse(RuleAction::Exclude, "title:Excluded").unwrap()],
+            ..PlanOptions::default()
+        };
+        let snapshot = snapshot(vec![
+            WindowInfo::for_test("Excluded", Rect::new(0, 0, 800, 600)),
+            WindowInfo::for_test("Shrunk", Rect::new(0, 0, 800, 600)),
+        ]);
+        let memory = SizeMemory {
+            windows: ["Excluded", "Shrunk"]
+                .map(|title| RememberedSize {
+                    hwnd: 1,
+                    title: title.to_string(),
+                    width: 1280,
+                    height: 720,
+                })
+                .to_vec(),
+        };
+
+        let mut plan = RelocationPlan::new(&snapshot, &options);
+        plan.grow_back(&snapshot, &memory);
+
+        assert_eq!(plan.moves.len(), 1);
+        assert_eq!(plan.moves[0].window.title, "Shrunk");
+        assert_eq!(plan.moves[0].new_rect, Rect::new(0, 0, 1280, 720));
+        assert_eq!(plan.skipped[0].window.title, "Excluded");
+        assert_eq!(plan.skipped[0].reason, SkipReason::Excluded(0));
+    }
+
+    #[test]
+    fn skips_windows_excluded_by_rules() {
+        let options = PlanOptions {
+            rules: vec![Rule::parse(RuleAction::Exclude, "title:Remote*").unwrap()],
+            ..PlanOptions::default()
+        };
+        let snapshot = snapshot(vec![
+            WindowInfo::for_test("Remote Desktop", Rect::new(-900, 300, -100, 900)),
+            WindowInfo::for_test("Lost", Rect::new(-900, 300, -100, 900)),
+        ]);
+
+        let plan = RelocationPlan::new(&snapshot, &options);
+
+        assert_eq!(plan.moves[0].window.title, "Lost");
+        assert_eq!(plan.skipped[0].reason, SkipReason::Excluded(0));
+    }
+
+    #[test]
+    fn plans_maximized_window_via_normal_rect() {
+        let mut lost = WindowInfo::for_test("Maximized", Rect::new(1912, -8, 3848, 1048));
+        lost.maximized = true;
+        lost.normal_rect = Rect::new(2200, 100, 2800, 500);
+        let plan = RelocationPlan::new(&snapshot(vec![lost]), &PlanOptions::default());
+
+        assert_eq!(
+            plan.moves[0].actions,
+            [Action::Restore, Action::Move, Action::Maximize]
+        );
+        assert_eq!(plan.moves[0].new_rect, Rect::new(0, 0, 600, 400));
+    }
+
+    #[test]
+    fn plans_restore_rect_of_minimized_window() {
+        let mut minimized =
+            WindowInfo::for_test("Minimized", Rect::new(-32000, -32000, -31840, -31972));
+        minimized.minimized = true;
+        minimized.normal_rect = Rect::new(2200, 100, 2800, 500);
+        let options = PlanOptions {
+            fit: true,
+            ..PlanOptions::default()
+        };
+
+        let plan = RelocationPlan::new(&snapshot(vec![minimized]), &options);
+
+        assert_eq!(plan.moves[0].actions, [Action::SetNormalRect]);
+        assert_eq!(plan.moves[0].new_rect, Rect::new(0, 0, 600, 400));
+    }
+
+    #[test]
+    fn skips_minimized_untitled_and_visible_windows() {
+        let mut minimized =
+            WindowInfo::for_test("Minimized", Rect::new(-32000, -32000, -31840, -31972));
+        minimized.minimized = true;
+        minimized.normal_rect = Rect::new(200, 200, 1000, 800);
+        let mut snapshot = snapshot(vec![
+            minimized,
+            WindowInfo::for_test("Visible", Rect::new(200, 200, 1000, 800)),
+        ]);
+        // Left out by the capture already
+        snapshot.skipped.push(Skipped {
+            window: WindowInfo::for_test("", Rect::default()),
+            reason: SkipReason::Untitled,
+        });
+        let plan = RelocationPlan::new(&snapshot, &PlanOptions::default());
+
+        assert!(plan.is_empty());
+        let reasons: Vec<_> = plan.skipped.iter().map(|skipped| skipped.reason).collect();
+        assert_eq!(
+            reasons,
+            [
+                SkipReason::Untitled,
+                SkipReason::Minimized,
+                SkipReason::OnScreen(1.0)
+            ]
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use anyhow::bail;
+
+use crate::error::Error;
+use crate::plan::{Action, PlannedMove, RelocationPlan};
+use crate::rescue::Parking;
+use crate::window::{WindowInfo, WindowKind};
+
+/// How a report is printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Summary line followed by one indented line per window
+    #[default]
+    Text,
+    /// One tab-separated line per window for scripts, see [`Report::tsv`]
+    Tsv,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "text" => Format::Text,
+            "tsv" => Format::Tsv,
+            _ => bail!("Unknown format {s:?}, expected text or tsv"),
+        })
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Text => write!(f, "text"),
+            Format::Tsv => write!(f, "tsv"),
+        }
+    }
+}
+
+/// Why the planner left a window alone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SkipReason {
+    Minimized,
+    Untitled,
+    NearTopLeft,
+    OnScreen(f32),
+    /// Pixels of title bar on screen
+    TitleBarVisible(i32),
+    TitleBarReachable,
+    Parked(Parking),
+    /// Not a kind of window the scope allows
+    OutOfScope(WindowKind),
+    /// Index of the exclude rule that matched
+    Excluded(usize),
+    /// There are include rules and none of them matched
+    NotIncluded,
+    /// Lost, but another window was asked for
+    NotTargeted,
+    /// Owned by a window that is left alone for another reason than being on screen
+    OwnerSkipped,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Minimized => write!(f, "minimized"),
+            SkipReason::Untitled => write!(f, "no title"),
+            SkipReason::NearTopLeft => write!(f, "already near the top-left corner"),
+            SkipReason::OnScreen(percent) => write!(f, "{:.2}% on screen", percent * 100.0),
+            SkipReason::TitleBarVisible(pixels) => write!(f, "{pixels}px of title bar on screen"),
+            SkipReason::TitleBarReachable => write!(f, "title bar can be grabbed"),
+            SkipReason::Parked(parking) => write!(f, "{parking}"),
+            SkipReason::OutOfScope(kind) => write!(f, "{kind}"),
+            SkipReason::Excluded(rule) => write!(f, "excluded by rule {}", rule + 1),
+            SkipReason::NotIncluded => write!(f, "matches no include rule"),
+            SkipReason::NotTargeted => write!(f, "not targeted"),
+            SkipReason::OwnerSkipped => write!(f, "owner is left alone"),
+        }
+    }
+}
+
+impl SkipReason {
+    /// Whether the window was left alone because enough of it can be seen or grabbed
+    pub fn is_on_screen(&self) -> bool {
+        matches!(
+            self,
+            SkipReason::NearTopLeft
+                | SkipReason::OnScreen(_)
+                | SkipReason::TitleBarVisible(_)
+                | SkipReason::TitleBarReachable
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+    pub window: WindowInfo,
+    pub reason: SkipReason,
+}
+
+/// Outcome of a run, one entry per window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    /// Nothing was applied, `moved` lists what would have been
+    pub dry_run: bool,
+    pub moved: Vec<PlannedMove>,
+    pub skipped: Vec<Skipped>,
+    pub failed: Vec<Error>,
+}
+
+impl Report {
+    /// Start a report with everything the plan already decided not to move
+    pub fn new(plan: &RelocationPlan) -> Self {
+        Self {
+            dry_run: false,
+            moved: Vec::new(),
+            skipped: plan.skipped.clone(),
+            failed: plan.failures.clone(),
+        }
+    }
+
+    /// Report a plan as if it had been applied successfully
+    pub fn preview(plan: &RelocationPlan) -> Self {
+        Self {
+            dry_run: true,
+            moved: plan.moves.clone(),
+            ..Self::new(plan)
+        }
+    }
+
+    pub fn record(&mut self, planned: &PlannedMove, result: Result<(), Error>) {
+        match result {
+            Ok(()) => self.moved.push(planned.clone()),
+            Err(e) => self.failed.push(e),
+        }
+    }
+
+    pub fn has_failures(&self) -> bool {
+        !self.failed.is_empty()
+    }
+
+    /// Exit code of the first failure, 0 when everything went through
+    pub fn exit_code(&self) -> i32 {
+        self.failed.first().map_or(0, Error::exit_code)
+    }
+
+    /// One line per window, the status, handle and title, then
+    ///
+    /// - for moved windows the new left, top, width and height
+    /// - for skipped windows the reason
+    /// - for failures the error
+    ///
+    /// Tabs in titles are replaced by spaces.
+    pub fn tsv(&self) -> String {
+        let clean = |title: &str| title.replace('\t', " ");
+        let moved = if self.dry_run { "would-move" } else { "moved" };
+        let mut tsv = String::new();
+        for planned in &self.moved {
+            let rect = planned.new_rect;
+            let _ = writeln!(
+                tsv,
+                "{moved}\t{}\t{}\t{}\t{}\t{}\t{}",
+                planned.window.id.0,
+                clean(&planned.window.title),
+                rect.left,
+                rect.top,
+                rect.width(),
+                rect.height()
+            );
+        }
+        for skipped in &self.skipped {
+            let _ = writeln!(
+                tsv,
+                "skipped\t{}\t{}\t{}",
+                skipped.window.id.0,
+                clean(&skipped.window.title),
+                skipped.reason
+            );
+        }
+        for error in &self.failed {
+            let (hwnd, title) = match error.window() {
+                Some(window) => (
+                    window.id.0.to_string(),
+                    clean(window.title.as_deref().unwrap_or_default()),
+                ),
+                None => (String::new(), String::new()),
+            };
+            let _ = writeln!(tsv, "failed\t{hwnd}\t{title}\t{error}");
+        }
+        tsv
+    }
+}
+
+/// The summary and one line per window, untitled windows only with `{:#}`
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let moved = if self.dry_run { "would move" } else { "moved" };
+        // There are dozens of untitled windows on a typical desktop
+        let verbose = f.alternate();
+        let skipped: Vec<_> = self
+            .skipped
+            .iter()
+            .filter(|skipped| verbose || skipped.reason != SkipReason::Untitled)
+            .collect();
+        writeln!(
+            f,
+            "Summary: {moved} {}, skipped {}, failed {}",
+            self.moved.len(),
+            skipped.len(),
+            self.failed.len()
+        )?;
+        for planned in &self.moved {
+            writeln!(
+                f,
+                "  {moved} {:?} {:?}",
+                planned.window.title, planned.window.id
+            )?;
+        }
+        for skipped in skipped {
+            writeln!(
+                f,
+                "  skipped {:?} {:?}: {}",
+                skipped.window.title, skipped.window.id, skipped.reason
+            )?;
+        }
+        for error in &self.failed {
+            writeln!(f, "  failed {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The line printed for a window as it is moved
+impl fmt::Display for PlannedMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Title: {:?} Percent: {:.2}% {:?} {:?} Target: ({}, {})",
+            self.window.title,
+            self.display_percent * 100.0,
+            self.window.id,
+            self.old_rect,
+            self.new_rect.left,
+            self.new_rect.top
+        )?;
+        if self.actions.contains(&Action::Maximize) {
+            write!(f, " Maximized")?;
+        }
+        if self.actions.contains(&Action::SetNormalRect) {
+            write!(f, " Minimized")?;
+        }
+        if let Some(tile) = self.tile {
+            write!(
+                f,
+                " Tile: row {} column {} size {}x{}",
+                tile.row,
+                tile.column,
+                self.new_rect.width(),
+                self.new_rect.height()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::{FakeWindow, FakeWindowSystem, Fault};
+    use crate::geometry::Rect;
+    use crate::plan::PlanOptions;
+    use crate::rescue::rescue_windows;
+
+    #[test]
+    fn tab_separated_lines() {
+        let mut system = FakeWindowSystem::new(1920, 1080);
+        let lost = Rect::new(3000, 200, 3800, 800);
+        system.add(FakeWindow::new("Lost\tfound", lost));
+        system.add(FakeWindow::new("Visible", Rect::new(200, 200, 1000, 800)));
+        system.add(FakeWindow::new("Stuck", lost).failing(Fault::Move));
+
+        let report = rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        let lines: Vec<_> = report.tsv().lines().map(str::to_string).collect();
+        assert_eq!(lines[0], "moved\t1\tLost found\t0\t0\t800\t600");
+        assert_eq!(lines[1], "skipped\t2\tVisible\t100.00% on screen");
+        assert!(lines[2].starts_with("failed\t3\tStuck\t"), "{}", lines[2]);
+        assert_eq!("tsv".parse::<Format>().unwrap(), Format::Tsv);
+        assert!("json".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn lists_untitled_windows_when_verbose() {
+        let mut system = FakeWindowSystem::new(1920, 1080);
+        system.add(FakeWindow::new("Visible", Rect::new(200, 200, 1000, 800)));
+        system.add(FakeWindow::new("", Rect::new(0, 0, 10, 10)));
+
+        let report = rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(
+            report.to_string(),
+            "Summary: moved 0, skipped 1, failed 0\n  \
+             skipped \"Visible\" WindowId(1): 100.00% on screen\n"
+        );
+        assert_eq!(
+            format!("{report:#}"),
+            "Summary: moved 0, skipped 2, failed 0\n  \
+             skipped \"\" WindowId(2): no title\n  \
+             skipped \"Visible\" WindowId(1): 100.00% on screen\n"
+        );
+        assert!(report.tsv().contains("skipped\t2\t\tno title"));
+    }
+}
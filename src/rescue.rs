@@ -0,0 +1,1075 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::bail;
+use serde::Deserialize;
+
+use crate::backend::{Monitor, WindowSystem};
+use crate::error::Error;
+use crate::geometry::{get_display_percent, visible_title_bar, Rect, RectCalc, TOP_LEFT_BOUND};
+use crate::plan::{apply, PlanOptions, RelocationPlan};
+use crate::report::{Report, SkipReason};
+use crate::window::{Snapshot, WindowInfo};
+
+/// Where Windows parks minimized windows, and some apps their helper windows
+pub const PARKING_SENTINEL: (i32, i32) = (-32000, -32000);
+
+/// How far left of or above every monitor a window has to be to count as parked on purpose
+///
+/// Monitors removed by a display change leave windows at most a monitor size away.
+pub const PARKING_DISTANCE: i32 = 10000;
+
+/// Why a window far off-screen was put there on purpose
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parking {
+    /// At the coordinates Windows uses for minimized windows
+    Sentinel,
+    /// Far beyond any monitor
+    FarAway,
+}
+
+impl fmt::Display for Parking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Parking::Sentinel => write!(f, "parked at the minimized position"),
+            Parking::FarAway => write!(f, "parked far off-screen"),
+        }
+    }
+}
+
+/// Tell windows parked on purpose apart from windows lost after a display change
+pub fn classify_parking(rect: Rect, monitors: &[Rect]) -> Option<Parking> {
+    if (rect.left, rect.top) == PARKING_SENTINEL {
+        return Some(Parking::Sentinel);
+    }
+    let left = monitors.iter().map(|monitor| monitor.left).min()?;
+    let top = monitors.iter().map(|monitor| monitor.top).min()?;
+    (rect.right <= left - PARKING_DISTANCE || rect.bottom <= top - PARKING_DISTANCE)
+        .then_some(Parking::FarAway)
+}
+
+/// How lost windows are told apart from the ones the user can still get hold of
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Detection {
+    /// Share of the window area on screen, or visible title bar pixels, see [`Thresholds`]
+    #[default]
+    Area,
+    /// Whether part of the title bar can be grabbed on some work area
+    Reachable,
+}
+
+impl FromStr for Detection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "area" => Detection::Area,
+            "reachable" => Detection::Reachable,
+            _ => bail!("Unknown detection {s:?}, expected area or reachable"),
+        })
+    }
+}
+
+impl TryFrom<String> for Detection {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Detection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Detection::Area => write!(f, "area"),
+            Detection::Reachable => write!(f, "reachable"),
+        }
+    }
+}
+
+/// Width of the minimize, maximize and close buttons at the right end of a title bar
+pub const CAPTION_BUTTONS_WIDTH: i32 = 140;
+
+/// Pixels of title bar needed to get hold of a window with the mouse
+pub const MIN_GRAB_WIDTH: i32 = 40;
+
+/// Whether enough of the draggable part of the title bar lies on a work area
+///
+/// Work areas leave out the taskbar, a caption hidden behind it cannot be grabbed.
+pub fn is_reachable(rect: Rect, work_areas: &[Rect]) -> bool {
+    let bar = rect.title_bar();
+    let buttons = CAPTION_BUTTONS_WIDTH.min(bar.width() / 2);
+    let draggable = Rect::new(bar.left, bar.top, bar.right - buttons, bar.bottom);
+    let needed = MIN_GRAB_WIDTH.min(draggable.width()).max(1);
+    work_areas
+        .iter()
+        .filter_map(|area| draggable.intersection(area))
+        .any(|grabbable| grabbable.width() >= needed)
+}
+
+pub const DEFAULT_MIN_VISIBLE: f32 = 0.5;
+
+/// Cutoffs deciding whether a window is lost
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// A window with more than this fraction of its area on screen stays
+    pub min_visible: f32,
+    /// A window starting at most this many pixels right of and below a monitor's top-left
+    /// corner stays
+    pub top_left_bound: i32,
+    /// When set, a window with at least this many pixels of title bar on screen stays,
+    /// instead of looking at `min_visible`
+    pub title_bar_pixels: Option<i32>,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            min_visible: DEFAULT_MIN_VISIBLE,
+            top_left_bound: TOP_LEFT_BOUND,
+            title_bar_pixels: None,
+        }
+    }
+}
+
+impl Thresholds {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(0.0..1.0).contains(&self.min_visible) {
+            bail!(
+                "Minimum visible fraction must be at least 0 and below 1, got {}",
+                self.min_visible
+            );
+        }
+        if self.top_left_bound < 0 {
+            bail!(
+                "Top-left bound must not be negative, got {}",
+                self.top_left_bound
+            );
+        }
+        if let Some(pixels) = self.title_bar_pixels.filter(|&pixels| pixels <= 0) {
+            bail!("Title bar pixels must be positive, got {pixels}");
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Thresholds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.title_bar_pixels {
+            Some(pixels) => write!(f, "on screen with {pixels}px of title bar visible")?,
+            None => write!(
+                f,
+                "on screen when more than {:.0}% visible",
+                self.min_visible * 100.0
+            )?,
+        }
+        write!(
+            f,
+            " or within {}px of a monitor's top-left corner",
+            self.top_left_bound
+        )
+    }
+}
+
+/// Thresholds for the windows on one monitor or of one application
+///
+/// Every condition given has to match. Later overrides win over earlier ones.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ThresholdOverride {
+    /// Index of the monitor most of the window is on
+    pub monitor: Option<usize>,
+    /// Text the window title contains
+    pub title: Option<String>,
+    pub min_visible: Option<f32>,
+    pub top_left_bound: Option<i32>,
+    pub title_bar_pixels: Option<i32>,
+}
+
+impl ThresholdOverride {
+    pub fn matches(&self, window: &WindowInfo, rect: Rect, monitors: &[Rect]) -> bool {
+        self.monitor
+            .is_none_or(|monitor| monitor_of(rect, monitors) == Some(monitor))
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|title| window.title.contains(title.as_str()))
+    }
+
+    pub fn apply(&self, thresholds: &mut Thresholds) {
+        if let Some(min_visible) = self.min_visible {
+            thresholds.min_visible = min_visible;
+        }
+        if let Some(top_left_bound) = self.top_left_bound {
+            thresholds.top_left_bound = top_left_bound;
+        }
+        if self.title_bar_pixels.is_some() {
+            thresholds.title_bar_pixels = self.title_bar_pixels;
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.monitor.is_none() && self.title.is_none() {
+            bail!("Threshold override needs a monitor or a title");
+        }
+        let mut thresholds = Thresholds::default();
+        self.apply(&mut thresholds);
+        thresholds.validate()
+    }
+}
+
+impl fmt::Display for ThresholdOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut conditions = Vec::new();
+        if let Some(monitor) = self.monitor {
+            conditions.push(format!("monitor {monitor}"));
+        }
+        if let Some(title) = &self.title {
+            conditions.push(format!("title contains {title:?}"));
+        }
+        let mut values = Vec::new();
+        if let Some(min_visible) = self.min_visible {
+            values.push(format!("min-visible {min_visible}"));
+        }
+        if let Some(top_left_bound) = self.top_left_bound {
+            values.push(format!("top-left-bound {top_left_bound}"));
+        }
+        if let Some(title_bar_pixels) = self.title_bar_pixels {
+            values.push(format!("title-bar-pixels {title_bar_pixels}"));
+        }
+        write!(f, "{}: {}", conditions.join(", "), values.join(", "))
+    }
+}
+
+/// Index of the monitor with the largest part of `rect`
+pub fn monitor_of(rect: Rect, monitors: &[Rect]) -> Option<usize> {
+    monitors
+        .iter()
+        .enumerate()
+        .filter_map(|(index, monitor)| Some((index, rect.intersection(monitor)?.area())))
+        .max_by_key(|&(_, area)| area)
+        .map(|(index, _)| index)
+}
+
+/// Thresholds that apply to a window judged at `rect`
+pub fn thresholds_for(
+    window: &WindowInfo,
+    rect: Rect,
+    monitors: &[Rect],
+    thresholds: &Thresholds,
+    overrides: &[ThresholdOverride],
+) -> Thresholds {
+    let mut thresholds = *thresholds;
+    for threshold_override in overrides {
+        if threshold_override.matches(window, rect, monitors) {
+            threshold_override.apply(&mut thresholds);
+        }
+    }
+    thresholds
+}
+
+/// Why a window at `rect` counts as on-screen, `None` if it is lost
+pub(crate) fn on_screen_reason(
+    rect: Rect,
+    monitors: &[Rect],
+    display_percent: f32,
+    thresholds: &Thresholds,
+) -> Option<SkipReason> {
+    if rect.left_top_within(monitors, thresholds.top_left_bound) {
+        return Some(SkipReason::NearTopLeft);
+    }
+    match thresholds.title_bar_pixels {
+        Some(pixels) => {
+            // A narrow window cannot show more title bar than it is wide
+            let visible = visible_title_bar(rect, monitors);
+            (visible >= pixels.min(rect.width())).then_some(SkipReason::TitleBarVisible(visible))
+        }
+        None => (display_percent > thresholds.min_visible)
+            .then_some(SkipReason::OnScreen(display_percent)),
+    }
+}
+
+/// Whether a window is far enough off-screen to be moved back, with the default thresholds
+pub fn is_off_screen(window: &WindowInfo, monitors: &[Monitor]) -> bool {
+    let monitors = monitor_rects(monitors);
+    let display_percent = get_display_percent(window.rect, &monitors);
+    classify_parking(window.rect, &monitors).is_none()
+        && on_screen_reason(
+            window.rect,
+            &monitors,
+            display_percent,
+            &Thresholds::default(),
+        )
+        .is_none()
+}
+
+pub(crate) fn monitor_rects(monitors: &[Monitor]) -> Vec<Rect> {
+    monitors.iter().map(|monitor| monitor.rect).collect()
+}
+
+/// Move every visible off-screen window back and report what happened to each
+///
+/// Only a failure to enumerate windows at all is returned as an error.
+pub fn rescue_windows(
+    system: &mut impl WindowSystem,
+    options: &PlanOptions,
+) -> Result<Report, Error> {
+    let plan = RelocationPlan::new(&Snapshot::capture(system)?, options);
+    Ok(apply(system, &plan))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::{Call, Edge, FakeWindow, FakeWindowSystem, Fault};
+    use crate::backend::WindowId;
+    use crate::error::WindowContext;
+    use crate::layout::Layout;
+    use crate::placement::PlacementStrategy;
+    use crate::rules::{Rule, RuleAction, Target};
+    use crate::window::{Scope, WindowKind};
+
+    fn desktop() -> FakeWindowSystem {
+        FakeWindowSystem::new(1920, 1080)
+    }
+
+    #[test]
+    fn classifies_parking() {
+        const PRIMARY: Rect = Rect::new(0, 0, 1920, 1080);
+        const LEFT: Rect = Rect::new(-2560, -360, 0, 1080);
+        let cases = [
+            (
+                "minimized sentinel",
+                Rect::new(-32000, -32000, -31840, -31972),
+                &[PRIMARY][..],
+                Some(Parking::Sentinel),
+            ),
+            (
+                "sentinel with a real size",
+                Rect::new(-32000, -32000, -31200, -31400),
+                &[PRIMARY],
+                Some(Parking::Sentinel),
+            ),
+            (
+                "far left",
+                Rect::new(-32000, 200, -31200, 800),
+                &[PRIMARY],
+                Some(Parking::FarAway),
+            ),
+            (
+                "far above",
+                Rect::new(100, -25000, 900, -24400),
+                &[PRIMARY],
+                Some(Parking::FarAway),
+            ),
+            (
+                "far above a monitor with a negative origin",
+                Rect::new(100, -12000, 900, -11400),
+                &[PRIMARY, LEFT],
+                Some(Parking::FarAway),
+            ),
+            (
+                "left of a disconnected monitor",
+                Rect::new(-2000, 200, -1200, 800),
+                &[PRIMARY],
+                None,
+            ),
+            (
+                "right of a disconnected monitor",
+                Rect::new(5000, 200, 5800, 800),
+                &[PRIMARY],
+                None,
+            ),
+            (
+                "just short of the parking distance",
+                Rect::new(-10800, 200, -9999, 800),
+                &[PRIMARY],
+                None,
+            ),
+            (
+                "far right is never parked",
+                Rect::new(30000, 200, 30800, 800),
+                &[PRIMARY],
+                None,
+            ),
+            (
+                "on screen",
+                Rect::new(200, 200, 1000, 800),
+                &[PRIMARY],
+                None,
+            ),
+        ];
+
+        for (name, rect, monitors, expected) in cases {
+            assert_eq!(classify_parking(rect, monitors), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn custom_thresholds() {
+        const PRIMARY: Rect = Rect::new(0, 0, 1920, 1080);
+        let strict = Thresholds {
+            min_visible: 0.9,
+            top_left_bound: 10,
+            title_bar_pixels: None,
+        };
+        let rect = Rect::new(1500, 50, 2100, 650);
+        let percent = get_display_percent(rect, &[PRIMARY]);
+
+        assert!(on_screen_reason(rect, &[PRIMARY], percent, &Thresholds::default()).is_some());
+        assert_eq!(on_screen_reason(rect, &[PRIMARY], percent, &strict), None);
+        let near = Rect::new(50, 50, 850, 650);
+        assert_eq!(
+            on_screen_reason(near, &[PRIMARY], 1.0, &strict),
+            Some(SkipReason::OnScreen(1.0))
+        );
+        assert_eq!(
+            on_screen_reason(Rect::new(5, 5, 805, 605), &[PRIMARY], 1.0, &strict),
+            Some(SkipReason::NearTopLeft)
+        );
+    }
+
+    #[test]
+    fn title_bar_criterion() {
+        const PRIMARY: Rect = Rect::new(0, 0, 1920, 1080);
+        let thresholds = Thresholds {
+            title_bar_pixels: Some(200),
+            ..Thresholds::default()
+        };
+        let mostly_off = Rect::new(1700, 500, 2700, 1500);
+        let caption_above = Rect::new(500, -300, 1300, 700);
+        let narrow = Rect::new(1800, 500, 1950, 900);
+
+        let reason = |rect| {
+            let percent = get_display_percent(rect, &[PRIMARY]);
+            on_screen_reason(rect, &[PRIMARY], percent, &thresholds)
+        };
+        assert_eq!(reason(mostly_off), Some(SkipReason::TitleBarVisible(220)));
+        assert_eq!(reason(caption_above), None);
+        assert_eq!(reason(narrow), None);
+        assert_eq!(
+            reason(Rect::new(1800, 500, 1920, 900)),
+            Some(SkipReason::TitleBarVisible(120))
+        );
+    }
+
+    #[test]
+    fn overrides_by_monitor_and_title() {
+        let monitors = [Rect::new(0, 0, 1920, 1080), Rect::new(1920, 0, 3840, 1080)];
+        let overrides = [
+            ThresholdOverride {
+                monitor: Some(1),
+                min_visible: Some(0.9),
+                ..ThresholdOverride::default()
+            },
+            ThresholdOverride {
+                title: Some("Remote".to_string()),
+                min_visible: Some(0.1),
+                ..ThresholdOverride::default()
+            },
+        ];
+        let resolve = |title, rect| {
+            thresholds_for(
+                &WindowInfo::for_test(title, rect),
+                rect,
+                &monitors,
+                &Thresholds::default(),
+                &overrides,
+            )
+            .min_visible
+        };
+
+        assert_eq!(resolve("Editor", Rect::new(100, 100, 900, 700)), 0.5);
+        assert_eq!(resolve("Editor", Rect::new(2000, 100, 2800, 700)), 0.9);
+        assert_eq!(
+            resolve("Remote Desktop", Rect::new(2000, 100, 2800, 700)),
+            0.1
+        );
+        assert_eq!(
+            resolve("Remote Desktop", Rect::new(-900, 100, -100, 700)),
+            0.1
+        );
+    }
+
+    #[test]
+    fn validates_thresholds() {
+        assert!(Thresholds::default().validate().is_ok());
+        let invalid = [
+            Thresholds {
+                min_visible: 1.0,
+                ..Thresholds::default()
+            },
+            Thresholds {
+                min_visible: -0.1,
+                ..Thresholds::default()
+            },
+            Thresholds {
+                top_left_bound: -1,
+                ..Thresholds::default()
+            },
+            Thresholds {
+                title_bar_pixels: Some(0),
+                ..Thresholds::default()
+            },
+        ];
+        for thresholds in invalid {
+            assert!(thresholds.validate().is_err(), "{thresholds:?}");
+        }
+
+        let unconditional = ThresholdOverride {
+            min_visible: Some(0.2),
+            ..ThresholdOverride::default()
+        };
+        assert!(unconditional.validate().is_err());
+    }
+
+    #[test]
+    fn reachability() {
+        const WORK_AREA: Rect = Rect::new(0, 0, 1920, 1040);
+        let cases = [
+            ("fully visible", Rect::new(200, 200, 1000, 800), true),
+            (
+                "caption above the top",
+                Rect::new(200, -100, 1000, 700),
+                false,
+            ),
+            ("caption partly above", Rect::new(200, -20, 1000, 780), true),
+            (
+                "caption behind the taskbar",
+                Rect::new(200, 1045, 1000, 1600),
+                false,
+            ),
+            (
+                "only the caption buttons",
+                Rect::new(-700, 300, 100, 900),
+                false,
+            ),
+            (
+                "enough left of the buttons",
+                Rect::new(1700, 300, 2500, 900),
+                true,
+            ),
+            (
+                "too little left of the buttons",
+                Rect::new(1900, 300, 2700, 900),
+                false,
+            ),
+            ("narrow window", Rect::new(1890, 300, 1950, 900), true),
+        ];
+
+        for (name, rect, expected) in cases {
+            assert_eq!(is_reachable(rect, &[WORK_AREA]), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn parses_detection_names() {
+        for name in ["area", "reachable"] {
+            assert_eq!(name.parse::<Detection>().unwrap().to_string(), name);
+        }
+        assert!("caption".parse::<Detection>().is_err());
+    }
+
+    #[test]
+    fn leaves_parked_windows_alone() {
+        let mut system = desktop();
+        system.add(FakeWindow::new(
+            "Sentinel",
+            Rect::new(-32000, -32000, -31840, -31972),
+        ));
+        system.add(FakeWindow::new("Helper", Rect::new(-32000, 0, -31000, 100)));
+        let lost = system.add(FakeWindow::new("Lost", Rect::new(-2000, 200, -1200, 800)));
+
+        let report = rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(system.calls(), [Call::Move(lost, 0, 0)]);
+        let reasons: Vec<_> = report
+            .skipped
+            .iter()
+            .map(|skipped| skipped.reason)
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                SkipReason::Parked(Parking::Sentinel),
+                SkipReason::Parked(Parking::FarAway)
+            ]
+        );
+    }
+
+    #[test]
+    fn moves_off_screen_window_to_origin() {
+        let mut system = desktop();
+        let lost = system.add(FakeWindow::new("Lost", Rect::new(3000, 200, 3800, 800)));
+
+        rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(system.calls(), [Call::Move(lost, 0, 0)]);
+        assert_eq!(system.window(lost).rect, Rect::new(0, 0, 800, 600));
+    }
+
+    #[test]
+    fn leaves_on_screen_windows_alone() {
+        let mut system = desktop();
+        system.add(FakeWindow::new("Visible", Rect::new(200, 200, 1000, 800)));
+        system.add(FakeWindow::new(
+            "Mostly visible",
+            Rect::new(1500, 200, 2100, 800),
+        ));
+        system.add(FakeWindow::new("Near origin", Rect::new(0, 50, 5000, 5000)));
+
+        rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert!(system.calls().is_empty());
+    }
+
+    #[test]
+    fn keeps_title_bar_clear_of_docked_bars() {
+        let mut system = desktop();
+        system.reserve(0, Edge::Top, 40);
+        system.reserve(0, Edge::Left, 60);
+        let lost = system.add(FakeWindow::new("Lost", Rect::new(3000, 200, 3800, 800)));
+
+        rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(system.calls(), [Call::Move(lost, 60, 40)]);
+    }
+
+    #[test]
+    fn leaves_windows_on_secondary_monitors_alone() {
+        let mut system = desktop();
+        system.add_monitor(Rect::new(1920, 0, 3840, 1080));
+        system.add_monitor(Rect::new(0, 1080, 1920, 2160));
+        system.add(FakeWindow::new("Right", Rect::new(2500, 200, 3300, 800)));
+        system.add(FakeWindow::new("Below", Rect::new(200, 1200, 1000, 1800)));
+        system.add(FakeWindow::new(
+            "Spanning",
+            Rect::new(1800, 900, 2200, 1300),
+        ));
+        let lost = system.add(FakeWindow::new("Lost", Rect::new(2500, 1200, 3300, 1800)));
+
+        rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(system.calls(), [Call::Move(lost, 0, 0)]);
+    }
+
+    #[test]
+    fn handles_negative_origins_and_layout_holes() {
+        let mut system = desktop();
+        system.add_monitor(Rect::new(-2560, -360, 0, 1080));
+        system.add_monitor(Rect::new(1920, -1080, 3840, 0));
+        system.add(FakeWindow::new("Left", Rect::new(-2000, -200, -1200, 400)));
+        system.add(FakeWindow::new(
+            "Upper right",
+            Rect::new(2200, -900, 3000, -300),
+        ));
+        let hole = system.add(FakeWindow::new("Hole", Rect::new(2200, 200, 3000, 800)));
+        let above = system.add(FakeWindow::new("Above", Rect::new(200, -900, 1000, -300)));
+
+        rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(
+            system.calls(),
+            [Call::Move(hole, 0, 0), Call::Move(above, 0, 0)]
+        );
+    }
+
+    #[test]
+    fn applies_rules_by_class_and_process() {
+        let mut system = desktop();
+        let rect = Rect::new(3000, 200, 3800, 800);
+        system.add(
+            FakeWindow::new("Remote Desktop", rect)
+                .class("TscShellContainerClass")
+                .process("mstsc.exe"),
+        );
+        let lost = system.add(FakeWindow::new("Notepad", rect).process("notepad.exe"));
+        let options = PlanOptions {
+            rules: vec![Rule::parse(RuleAction::Exclude, "process:MSTSC.exe").unwrap()],
+            ..PlanOptions::default()
+        };
+
+        let report = rescue_windows(&mut system, &options).unwrap();
+
+        assert_eq!(system.calls(), [Call::Move(lost, 0, 0)]);
+        assert_eq!(report.skipped[0].reason, SkipReason::Excluded(0));
+    }
+
+    #[test]
+    fn rescues_only_app_windows_by_default() {
+        let mut system = desktop();
+        let rect = Rect::new(3000, 200, 3800, 800);
+        let main = system.add(FakeWindow::new("Main", rect));
+        let tool = system.add(FakeWindow::new("Palette", rect).tool_window());
+        // Owner not enumerated, like a hidden main window
+        system.add(FakeWindow::new("Dialog", rect).owned_by(WindowId(99)));
+        system.add(FakeWindow::new("Other desktop", rect).cloaked());
+
+        let report = rescue_windows(&mut system.clone(), &PlanOptions::default()).unwrap();
+        let reasons: Vec<_> = report
+            .skipped
+            .iter()
+            .map(|skipped| skipped.reason)
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                SkipReason::OutOfScope(WindowKind::Tool),
+                SkipReason::OutOfScope(WindowKind::Owned),
+                SkipReason::OutOfScope(WindowKind::Cloaked),
+            ]
+        );
+
+        let options = PlanOptions {
+            scope: Scope {
+                tool_windows: true,
+                ..Scope::default()
+            },
+            ..PlanOptions::default()
+        };
+        rescue_windows(&mut system, &options).unwrap();
+        assert_eq!(
+            system.calls(),
+            [Call::Move(main, 0, 0), Call::Move(tool, 0, 0)]
+        );
+    }
+
+    #[test]
+    fn moves_owned_windows_with_their_owner() {
+        let mut system = desktop();
+        let main = system.add(FakeWindow::new("Main", Rect::new(3000, 200, 3800, 800)));
+        let dialog =
+            system.add(FakeWindow::new("Options", Rect::new(3100, 300, 3500, 600)).owned_by(main));
+        let palette = system.add(
+            FakeWindow::new("Colors", Rect::new(3700, 200, 3900, 400))
+                .tool_window()
+                .owned_by(main),
+        );
+        let nested =
+            system.add(FakeWindow::new("Font", Rect::new(3150, 350, 3300, 450)).owned_by(dialog));
+
+        rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(
+            system.calls(),
+            [
+                Call::Move(main, 0, 0),
+                Call::Move(dialog, 100, 100),
+                Call::Move(palette, 700, 0),
+                Call::Move(nested, 150, 150),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_visible_dialog_of_lost_owner_alone() {
+        let mut system = desktop();
+        let main = system.add(FakeWindow::new("Main", Rect::new(3000, 200, 3800, 800)));
+        system.add(FakeWindow::new("Options", Rect::new(500, 300, 900, 600)).owned_by(main));
+
+        let report = rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(system.calls(), [Call::Move(main, 0, 0)]);
+        assert_eq!(report.skipped[0].reason, SkipReason::OnScreen(1.0));
+    }
+
+    #[test]
+    fn centers_orphaned_dialog_over_its_owner() {
+        let mut system = desktop();
+        let owner = system.add(FakeWindow::new("Main", Rect::new(200, 200, 1000, 800)));
+        let dialog =
+            system.add(FakeWindow::new("Save as", Rect::new(3000, 300, 3400, 600)).owned_by(owner));
+        let lost = system.add(FakeWindow::new("Lost", Rect::new(3000, 200, 3800, 800)));
+        let options = PlanOptions {
+            layout: Layout::Cascade,
+            ..PlanOptions::default()
+        };
+
+        rescue_windows(&mut system, &options).unwrap();
+
+        // Not part of the cascade
+        assert_eq!(
+            system.calls(),
+            [Call::Move(lost, 0, 0), Call::Move(dialog, 400, 350)]
+        );
+    }
+
+    #[test]
+    fn centers_orphaned_dialog_over_its_maximized_owner() {
+        let mut system = desktop();
+        let owner = system.add(
+            FakeWindow::new("Main", Rect::new(0, 0, 1920, 1080))
+                .maximized(Rect::new(3000, 200, 3800, 800)),
+        );
+        let dialog =
+            system.add(FakeWindow::new("Save as", Rect::new(3000, 300, 3400, 600)).owned_by(owner));
+
+        rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(system.calls(), [Call::Move(dialog, 760, 390)]);
+    }
+
+    #[test]
+    fn leaves_excluded_owned_windows_behind() {
+        let mut system = desktop();
+        let main = system.add(FakeWindow::new("Main", Rect::new(3000, 200, 3800, 800)));
+        system.add(FakeWindow::new("Find", Rect::new(3100, 300, 3500, 600)).owned_by(main));
+        let options = PlanOptions {
+            rules: vec![Rule::parse(RuleAction::Exclude, "title:Find").unwrap()],
+            ..PlanOptions::default()
+        };
+
+        rescue_windows(&mut system, &options).unwrap();
+
+        assert_eq!(system.calls(), [Call::Move(main, 0, 0)]);
+    }
+
+    #[test]
+    fn leaves_dialogs_of_excluded_owners_behind() {
+        let mut system = desktop();
+        let main = system.add(FakeWindow::new("Main", Rect::new(3000, 200, 3800, 800)));
+        let find =
+            system.add(FakeWindow::new("Find", Rect::new(3100, 300, 3500, 600)).owned_by(main));
+        system.add(FakeWindow::new("Nested", Rect::new(3150, 350, 3300, 450)).owned_by(find));
+        let options = PlanOptions {
+            rules: vec![Rule::parse(RuleAction::Exclude, "title:Main").unwrap()],
+            ..PlanOptions::default()
+        };
+
+        let report = rescue_windows(&mut system, &options).unwrap();
+
+        assert!(system.calls().is_empty());
+        let reasons: Vec<_> = report
+            .skipped
+            .iter()
+            .map(|skipped| skipped.reason)
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                SkipReason::Excluded(0),
+                SkipReason::OwnerSkipped,
+                SkipReason::OwnerSkipped
+            ]
+        );
+    }
+
+    fn target(configure: impl FnOnce(&mut Target)) -> PlanOptions {
+        let mut target = Target::default();
+        configure(&mut target);
+        PlanOptions {
+            target: Some(target),
+            ..PlanOptions::default()
+        }
+    }
+
+    #[test]
+    fn moves_only_targeted_windows() {
+        let mut system = desktop();
+        let rect = Rect::new(3000, 200, 3800, 800);
+        system.add(FakeWindow::new("Paint", rect).process("mspaint.exe"));
+        let notepad = system.add(FakeWindow::new("Notepad", rect).process("notepad.exe"));
+        // Targets ignore the scope
+        let palette = system.add(FakeWindow::new("Palette", rect).tool_window());
+
+        let options = target(|target| target.set_process("NOTEPAD.exe").unwrap());
+        let report = rescue_windows(&mut system, &options).unwrap();
+        assert_eq!(system.calls(), [Call::Move(notepad, 0, 0)]);
+        assert_eq!(report.skipped[0].reason, SkipReason::NotTargeted);
+
+        let options = target(|target| target.hwnd = Some(palette));
+        rescue_windows(&mut system, &options).unwrap();
+        assert_eq!(system.calls()[1..], [Call::Move(palette, 0, 0)]);
+    }
+
+    #[test]
+    fn force_moves_targeted_windows_on_screen() {
+        let mut system = desktop();
+        let visible = system.add(FakeWindow::new("Visible", Rect::new(200, 200, 1000, 800)));
+        let mut options = target(|target| target.set_title("visible").unwrap());
+
+        let report = rescue_windows(&mut system, &options).unwrap();
+        assert!(system.calls().is_empty());
+        assert_eq!(report.skipped[0].reason, SkipReason::OnScreen(1.0));
+
+        options.force = true;
+        rescue_windows(&mut system, &options).unwrap();
+        assert_eq!(system.calls(), [Call::Move(visible, 0, 0)]);
+    }
+
+    #[test]
+    fn skips_hidden_minimized_and_untitled_windows() {
+        let mut system = desktop();
+        let rect = Rect::new(-2000, -2000, -1000, -1000);
+        system.add(FakeWindow::new("Hidden", rect).hidden());
+        system.add(FakeWindow::new("Minimized", Rect::new(200, 200, 1000, 800)).minimized());
+        system.add(FakeWindow::new("", rect));
+
+        rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert!(system.calls().is_empty());
+    }
+
+    #[test]
+    fn fixes_restore_rect_of_minimized_window() {
+        let mut system = desktop();
+        let lost =
+            system.add(FakeWindow::new("Minimized", Rect::new(3000, 200, 3800, 800)).minimized());
+
+        rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(
+            system.calls(),
+            [Call::SetNormalRect(lost, Rect::new(0, 0, 800, 600))]
+        );
+        let window = system.window(lost);
+        assert!(window.minimized);
+        assert_eq!(window.rect, Rect::new(-32000, -32000, -31840, -31972));
+    }
+
+    #[test]
+    fn maximizes_again_after_moving() {
+        let mut system = desktop();
+        system.reserve(0, Edge::Bottom, 40);
+        let normal = Rect::new(2500, 100, 3100, 500);
+        let lost = system
+            .add(FakeWindow::new("Maximized", Rect::new(1912, -8, 3848, 1048)).maximized(normal));
+
+        rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(
+            system.calls(),
+            [
+                Call::Restore(lost),
+                Call::Move(lost, 0, 0),
+                Call::Maximize(lost)
+            ]
+        );
+        let window = system.window(lost);
+        assert!(window.maximized);
+        assert_eq!(window.rect, Rect::new(0, 0, 1920, 1040));
+        assert_eq!(window.normal_rect, Rect::new(0, 0, 600, 400));
+    }
+
+    #[test]
+    fn maximizes_on_the_chosen_monitor() {
+        let mut system = desktop();
+        let secondary = system.add_monitor(Rect::new(-1920, 0, 0, 1080));
+        let normal = Rect::new(2500, 100, 3100, 500);
+        let lost = system
+            .add(FakeWindow::new("Maximized", Rect::new(1912, -8, 3848, 1048)).maximized(normal));
+        let options = PlanOptions {
+            strategy: PlacementStrategy::Relative(secondary),
+            ..PlanOptions::default()
+        };
+
+        rescue_windows(&mut system, &options).unwrap();
+
+        assert_eq!(system.window(lost).rect, Rect::new(-1920, 0, 0, 1080));
+    }
+
+    #[test]
+    fn reports_missing_relative_monitor() {
+        let mut system = desktop();
+        let lost = system.add(FakeWindow::new("Lost", Rect::new(2500, 100, 3100, 500)));
+        let options = PlanOptions {
+            strategy: PlacementStrategy::Relative(1),
+            ..PlanOptions::default()
+        };
+
+        let report = rescue_windows(&mut system, &options).unwrap();
+
+        assert_eq!(system.window(lost).rect, Rect::new(580, 100, 1180, 500));
+        assert_eq!(report.failed, [Error::NoSuchMonitor { index: 1, count: 1 }]);
+        assert_eq!(report.exit_code(), 11);
+
+        // Nothing went to the primary monitor in its place
+        let report = rescue_windows(&mut system, &options).unwrap();
+        assert!(report.failed.is_empty());
+    }
+
+    #[test]
+    fn reports_failed_maximize() {
+        let mut system = desktop();
+        let normal = Rect::new(2500, 100, 3100, 500);
+        let lost = system.add(
+            FakeWindow::new("Maximized", Rect::new(1912, -8, 3848, 1048))
+                .maximized(normal)
+                .failing(Fault::Maximize),
+        );
+
+        let report = rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(system.window(lost).rect, Rect::new(0, 0, 600, 400));
+        assert_eq!(report.exit_code(), 10);
+    }
+
+    #[test]
+    fn tiles_windows_in_a_grid() {
+        let mut system = desktop();
+        let small = system.add(FakeWindow::new("Small", Rect::new(3000, 200, 3400, 500)));
+        let large = system.add(FakeWindow::new("Large", Rect::new(-2500, 0, -580, 1080)));
+        let options = PlanOptions {
+            layout: Layout::Grid,
+            ..PlanOptions::default()
+        };
+
+        rescue_windows(&mut system, &options).unwrap();
+
+        assert_eq!(
+            system.calls(),
+            [
+                Call::Move(small, 0, 0),
+                Call::Move(large, 960, 0),
+                Call::Resize(large, 960, 540)
+            ]
+        );
+        assert_eq!(system.window(large).rect, Rect::new(960, 0, 1920, 540));
+    }
+
+    #[test]
+    fn continues_past_failures() {
+        let mut system = desktop();
+        let rect = Rect::new(3000, 200, 3800, 800);
+        let unreadable = system.add(FakeWindow::new("Unreadable", rect).failing(Fault::Rect));
+        let broken = system.add(FakeWindow::new("Broken", rect).failing(Fault::Move));
+        let lost = system.add(FakeWindow::new("Lost", rect));
+
+        let report = rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert_eq!(system.calls(), [Call::Move(lost, 0, 0)]);
+        assert_eq!(report.moved.len(), 1);
+        let failed: Vec<_> = report
+            .failed
+            .iter()
+            .map(|error| (error.window().unwrap().id, error.exit_code()))
+            .collect();
+        assert_eq!(failed, [(unreadable, 5), (broken, 7)]);
+        assert_eq!(report.exit_code(), 5);
+    }
+
+    #[test]
+    fn reports_access_denied_and_hung_windows() {
+        let mut system = desktop();
+        let rect = Rect::new(3000, 200, 3800, 800);
+        let elevated = system.add(FakeWindow::new("Elevated", rect).elevated());
+        let hung = system.add(FakeWindow::new("Hung", rect).hung());
+
+        let report = rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+
+        assert!(system.calls().is_empty());
+        assert_eq!(
+            report.failed,
+            [
+                Error::AccessDenied {
+                    window: WindowContext::new(elevated, Some("Elevated")),
+                    operation: "SetWindowPos",
+                },
+                Error::HungWindow {
+                    window: WindowContext::new(hung, Some("Hung")),
+                },
+            ]
+        );
+    }
+}
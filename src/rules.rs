@@ -0,0 +1,374 @@
+use std::fmt;
+
+use anyhow::{anyhow, bail, Context};
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::backend::WindowId;
+use crate::report::SkipReason;
+use crate::window::WindowInfo;
+
+/// What a matching rule does with a window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Include,
+    Exclude,
+}
+
+impl fmt::Display for RuleAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleAction::Include => write!(f, "include"),
+            RuleAction::Exclude => write!(f, "exclude"),
+        }
+    }
+}
+
+/// How a rule matches the window title
+#[derive(Debug, Clone)]
+pub enum TitlePattern {
+    /// Case-insensitive, `*` and `?` wildcards
+    Glob(Pattern),
+    /// Matches anywhere in the title unless anchored
+    Regex(Regex),
+}
+
+impl PartialEq for TitlePattern {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (TitlePattern::Glob(a), TitlePattern::Glob(b)) => a == b,
+            (TitlePattern::Regex(a), TitlePattern::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+const IGNORE_CASE: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// Filter on windows, every condition given has to match
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "RuleConfig")]
+pub struct Rule {
+    pub action: RuleAction,
+    pub title: Option<TitlePattern>,
+    /// Case-insensitive glob on the window class name
+    pub class: Option<Pattern>,
+    /// Case-insensitive glob on the executable file name, like `mstsc.exe`
+    pub process: Option<Pattern>,
+}
+
+/// `[[rule]]` table of the config file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct RuleConfig {
+    action: RuleAction,
+    title: Option<String>,
+    title_regex: Option<String>,
+    class: Option<String>,
+    process: Option<String>,
+}
+
+fn glob(pattern: &str) -> anyhow::Result<Pattern> {
+    Pattern::new(pattern).with_context(|| format!("Invalid pattern {pattern:?}"))
+}
+
+fn regex(pattern: &str) -> anyhow::Result<Regex> {
+    Regex::new(pattern).with_context(|| format!("Invalid regex {pattern:?}"))
+}
+
+impl TryFrom<RuleConfig> for Rule {
+    type Error = anyhow::Error;
+
+    fn try_from(config: RuleConfig) -> Result<Self, Self::Error> {
+        let title = match (config.title, config.title_regex) {
+            (Some(_), Some(_)) => bail!("A rule can have title or title-regex, not both"),
+            (Some(title), None) => Some(TitlePattern::Glob(glob(&title)?)),
+            (None, Some(title)) => Some(TitlePattern::Regex(regex(&title)?)),
+            (None, None) => None,
+        };
+        let rule = Rule {
+            action: config.action,
+            title,
+            class: config.class.as_deref().map(glob).transpose()?,
+            process: config.process.as_deref().map(glob).transpose()?,
+        };
+        if rule.title.is_none() && rule.class.is_none() && rule.process.is_none() {
+            bail!("A rule needs a title, title-regex, class or process");
+        }
+        Ok(rule)
+    }
+}
+
+impl Rule {
+    /// Rule with a single condition written `<kind>:<pattern>`, as given on the command line
+    ///
+    /// Kinds are `title`, `title-regex`, `class` and `process`.
+    pub fn parse(action: RuleAction, condition: &str) -> anyhow::Result<Self> {
+        let (kind, pattern) = condition.split_once(':').ok_or_else(|| {
+            anyhow!("Invalid rule {condition:?}, expected title, title-regex, class or process:<pattern>")
+        })?;
+        let mut config = RuleConfig {
+            action,
+            title: None,
+            title_regex: None,
+            class: None,
+            process: None,
+        };
+        let field = match kind {
+            "title" => &mut config.title,
+            "title-regex" => &mut config.title_regex,
+            "class" => &mut config.class,
+            "process" => &mut config.process,
+            _ => bail!("Unknown rule kind {kind:?}, expected title, title-regex, class or process"),
+        };
+        *field = Some(pattern.to_string());
+        config.try_into()
+    }
+
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        let title = match &self.title {
+            Some(TitlePattern::Glob(pattern)) => pattern.matches_with(&window.title, IGNORE_CASE),
+            Some(TitlePattern::Regex(regex)) => regex.is_match(&window.title),
+            None => true,
+        };
+        title
+            && self
+                .class
+                .as_ref()
+                .is_none_or(|pattern| pattern.matches_with(&window.class, IGNORE_CASE))
+            && self
+                .process
+                .as_ref()
+                .is_none_or(|pattern| pattern.matches_with(&window.process, IGNORE_CASE))
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.action)?;
+        match &self.title {
+            Some(TitlePattern::Glob(pattern)) => write!(f, " title {:?}", pattern.as_str())?,
+            Some(TitlePattern::Regex(regex)) => write!(f, " title-regex {:?}", regex.as_str())?,
+            None => {}
+        }
+        if let Some(class) = &self.class {
+            write!(f, " class {:?}", class.as_str())?;
+        }
+        if let Some(process) = &self.process {
+            write!(f, " process {:?}", process.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+/// Windows picked on the command line by `moswb move`, every criterion given has to match
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Target {
+    pub hwnd: Option<WindowId>,
+    /// Case-insensitive glob on the title
+    pub title: Option<Pattern>,
+    /// Case-insensitive glob on the executable file name
+    pub process: Option<Pattern>,
+}
+
+impl Target {
+    pub fn is_empty(&self) -> bool {
+        self.hwnd.is_none() && self.title.is_none() && self.process.is_none()
+    }
+
+    pub fn set_title(&mut self, pattern: &str) -> anyhow::Result<()> {
+        self.title = Some(glob(pattern)?);
+        Ok(())
+    }
+
+    pub fn set_process(&mut self, pattern: &str) -> anyhow::Result<()> {
+        self.process = Some(glob(pattern)?);
+        Ok(())
+    }
+
+    /// Handles are accepted in decimal, or in hexadecimal with a `0x` prefix
+    pub fn set_hwnd(&mut self, handle: &str) -> anyhow::Result<()> {
+        let parsed = match handle.strip_prefix("0x").or(handle.strip_prefix("0X")) {
+            Some(hex) => isize::from_str_radix(hex, 16),
+            None => handle.parse(),
+        };
+        let hwnd = parsed.with_context(|| format!("Invalid window handle {handle:?}"))?;
+        self.hwnd = Some(WindowId(hwnd));
+        Ok(())
+    }
+
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        self.hwnd.is_none_or(|hwnd| hwnd == window.id)
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|pattern| pattern.matches_with(&window.title, IGNORE_CASE))
+            && self
+                .process
+                .as_ref()
+                .is_none_or(|pattern| pattern.matches_with(&window.process, IGNORE_CASE))
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut criteria = Vec::new();
+        if let Some(hwnd) = self.hwnd {
+            criteria.push(format!("hwnd {:#x}", hwnd.0));
+        }
+        if let Some(title) = &self.title {
+            criteria.push(format!("title {:?}", title.as_str()));
+        }
+        if let Some(process) = &self.process {
+            criteria.push(format!("process {:?}", process.as_str()));
+        }
+        write!(f, "{}", criteria.join(" "))
+    }
+}
+
+/// Why the rules leave a window alone, if they do
+///
+/// The first matching rule decides. A window no rule matches is left alone only when there
+/// are include rules, so they select the windows to rescue.
+pub fn rule_skip_reason(rules: &[Rule], window: &WindowInfo) -> Option<SkipReason> {
+    match rules.iter().position(|rule| rule.matches(window)) {
+        Some(index) => match rules[index].action {
+            RuleAction::Include => None,
+            RuleAction::Exclude => Some(SkipReason::Excluded(index)),
+        },
+        None => rules
+            .iter()
+            .any(|rule| rule.action == RuleAction::Include)
+            .then_some(SkipReason::NotIncluded),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Rect;
+
+    fn window(title: &str, class: &str, process: &str) -> WindowInfo {
+        WindowInfo {
+            class: class.to_string(),
+            process: process.to_string(),
+            ..WindowInfo::for_test(title, Rect::new(-900, 300, -100, 900))
+        }
+    }
+
+    fn rule(action: RuleAction, condition: &str) -> Rule {
+        Rule::parse(action, condition).unwrap()
+    }
+
+    #[test]
+    fn matches_each_condition() {
+        let remote = window(
+            "server - Remote Desktop Connection",
+            "TscShellContainerClass",
+            "mstsc.exe",
+        );
+        let cases = [
+            ("title:*remote desktop*", true),
+            ("title:Remote*", false),
+            ("title-regex:^server -", true),
+            ("title-regex:^Remote", false),
+            ("class:TscShell*", true),
+            ("class:Notepad", false),
+            ("process:MSTSC.EXE", true),
+            ("process:obs*.exe", false),
+        ];
+
+        for (condition, expected) in cases {
+            assert_eq!(
+                rule(RuleAction::Exclude, condition).matches(&remote),
+                expected,
+                "{condition}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_condition_has_to_match() {
+        let config = RuleConfig {
+            action: RuleAction::Exclude,
+            title: Some("*Recording*".to_string()),
+            title_regex: None,
+            class: None,
+            process: Some("obs64.exe".to_string()),
+        };
+        let rule = Rule::try_from(config).unwrap();
+
+        assert!(rule.matches(&window("Recording - OBS", "Qt", "obs64.exe")));
+        assert!(!rule.matches(&window("Recording.mp4", "Qt", "vlc.exe")));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = [
+            rule(RuleAction::Include, "title:*Important*"),
+            rule(RuleAction::Exclude, "process:mstsc.exe"),
+        ];
+
+        let important = window("Important - Remote Desktop", "", "mstsc.exe");
+        let remote = window("Remote Desktop", "", "mstsc.exe");
+        let other = window("Notepad", "", "notepad.exe");
+        assert_eq!(rule_skip_reason(&rules, &important), None);
+        assert_eq!(
+            rule_skip_reason(&rules, &remote),
+            Some(SkipReason::Excluded(1))
+        );
+        assert_eq!(
+            rule_skip_reason(&rules, &other),
+            Some(SkipReason::NotIncluded)
+        );
+    }
+
+    #[test]
+    fn excludes_only_without_include_rules() {
+        let rules = [rule(RuleAction::Exclude, "process:mstsc.exe")];
+        assert_eq!(
+            rule_skip_reason(&rules, &window("Notepad", "", "notepad.exe")),
+            None
+        );
+        assert_eq!(
+            rule_skip_reason(&[], &window("Notepad", "", "notepad.exe")),
+            None
+        );
+    }
+
+    #[test]
+    fn targets_by_handle_title_and_process() {
+        let notepad = window("notes.txt - Notepad", "Notepad", "notepad.exe");
+        let mut target = Target::default();
+        assert!(target.is_empty());
+
+        target.set_title("*notepad").unwrap();
+        assert!(target.matches(&notepad));
+        target.set_process("mstsc.exe").unwrap();
+        assert!(!target.matches(&notepad));
+
+        let mut target = Target::default();
+        target.set_hwnd("0x1").unwrap();
+        assert!(target.matches(&notepad));
+        target.set_hwnd("2").unwrap();
+        assert!(!target.matches(&notepad));
+        assert_eq!(target.to_string(), "hwnd 0x2");
+        assert!(target.set_hwnd("window").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for condition in ["mstsc.exe", "pid:42", "title-regex:(", "class:["] {
+            assert!(
+                Rule::parse(RuleAction::Exclude, condition).is_err(),
+                "{condition}"
+            );
+        }
+    }
+}
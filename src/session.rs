@@ -0,0 +1,220 @@
+//! Passes the way the command line runs them, with what is kept between runs
+
+use crate::backend::WindowSystem;
+use crate::error::Error;
+use crate::memory::SizeMemory;
+use crate::plan::{apply_move, PlanOptions, PlannedMove, RelocationPlan};
+use crate::report::Report;
+use crate::undo::UndoLog;
+use crate::window::Snapshot;
+
+/// What a pass does with the windows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pass {
+    /// Move lost windows back, and with `fit` grow shrunk ones back
+    #[default]
+    Rescue,
+    /// Put the windows moved by the last pass that moved any back where they were
+    Restore,
+}
+
+/// What is kept between runs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct State {
+    /// Remembered sizes of windows shrunk by `fit`
+    pub memory: SizeMemory,
+    /// Windows moved by the last pass that moved any
+    pub undo: UndoLog,
+}
+
+impl State {
+    /// Read the files in `%APPDATA%\moswb`
+    ///
+    /// A broken file is started over, the errors come back as warnings.
+    pub fn load() -> (Self, Vec<anyhow::Error>) {
+        let mut warnings = Vec::new();
+        let memory = match SizeMemory::default_path() {
+            Some(path) => SizeMemory::load(&path).unwrap_or_else(|e| {
+                warnings.push(e.context("Forgetting remembered window sizes"));
+                SizeMemory::default()
+            }),
+            None => SizeMemory::default(),
+        };
+        let undo = match UndoLog::default_path() {
+            Some(path) => UndoLog::load(&path).unwrap_or_else(|e| {
+                warnings.push(e.context("Nothing to restore"));
+                UndoLog::default()
+            }),
+            None => UndoLog::default(),
+        };
+        (Self { memory, undo }, warnings)
+    }
+
+    /// Write the parts that changed since `original`, returning the files that failed
+    pub fn save(&self, original: &State) -> Vec<anyhow::Error> {
+        let mut saved = Vec::new();
+        if self.memory != original.memory {
+            saved.extend(SizeMemory::default_path().map(|path| self.memory.save(&path)));
+        }
+        if self.undo != original.undo {
+            saved.extend(UndoLog::default_path().map(|path| self.undo.save(&path)));
+        }
+        saved.into_iter().filter_map(Result::err).collect()
+    }
+}
+
+/// Plan a pass and, unless `dry_run`, apply it and update `state`
+///
+/// `on_move` sees every planned window before it is moved. Only a failure to enumerate
+/// windows at all is returned as an error.
+pub fn run(
+    system: &mut impl WindowSystem,
+    pass: Pass,
+    dry_run: bool,
+    options: &PlanOptions,
+    state: &mut State,
+    mut on_move: impl FnMut(&PlannedMove),
+) -> Result<Report, Error> {
+    let snapshot = Snapshot::capture(system)?;
+    let plan = match pass {
+        Pass::Restore => state.undo.plan(&snapshot),
+        Pass::Rescue => {
+            let mut plan = RelocationPlan::new(&snapshot, options);
+            // Moving one window leaves every other window alone
+            if options.fit && options.target.is_none() {
+                plan.grow_back(&snapshot, &state.memory);
+            }
+            plan
+        }
+    };
+
+    if dry_run {
+        plan.moves.iter().for_each(&mut on_move);
+        return Ok(Report::preview(&plan));
+    }
+
+    let mut report = Report::new(&plan);
+    for planned in &plan.moves {
+        on_move(planned);
+        report.record(planned, apply_move(system, planned));
+    }
+    // Windows put back at their original size are no longer shrunk
+    if options.fit || pass == Pass::Restore {
+        state.memory.update(&report.moved);
+    }
+    if pass == Pass::Restore {
+        state.undo = UndoLog::default();
+    } else if !report.moved.is_empty() {
+        state.undo.record(&report.moved);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::{FakeWindow, FakeWindowSystem};
+    use crate::geometry::Rect;
+
+    #[test]
+    fn shrinks_and_grows_back() {
+        let mut system = FakeWindowSystem::new(1366, 768);
+        let big = system.add(FakeWindow::new("Big", Rect::new(3000, 0, 5560, 1440)));
+        let options = PlanOptions {
+            fit: true,
+            ..PlanOptions::default()
+        };
+        let mut state = State::default();
+
+        run(
+            &mut system,
+            Pass::Rescue,
+            false,
+            &options,
+            &mut state,
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(system.window(big).rect, Rect::new(0, 0, 1366, 768));
+
+        system.add_monitor(Rect::new(1366, 0, 3926, 1440));
+        system.window_mut(big).rect = Rect::new(1400, 0, 2766, 768);
+        run(
+            &mut system,
+            Pass::Rescue,
+            false,
+            &options,
+            &mut state,
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(system.window(big).rect, Rect::new(1366, 0, 3926, 1440));
+        assert_eq!(state.memory, SizeMemory::default());
+    }
+
+    #[test]
+    fn restores_the_last_run() {
+        let mut system = FakeWindowSystem::new(1920, 1080);
+        let lost = system.add(FakeWindow::new("Lost", Rect::new(3000, 200, 3800, 800)));
+        let options = PlanOptions::default();
+        let mut state = State::default();
+
+        run(
+            &mut system,
+            Pass::Rescue,
+            false,
+            &options,
+            &mut state,
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(system.window(lost).rect, Rect::new(0, 0, 800, 600));
+
+        // A pass that moves nothing keeps what there is to restore
+        run(
+            &mut system,
+            Pass::Rescue,
+            false,
+            &options,
+            &mut state,
+            |_| {},
+        )
+        .unwrap();
+        let report = run(
+            &mut system,
+            Pass::Restore,
+            false,
+            &options,
+            &mut state,
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(report.moved.len(), 1);
+        assert_eq!(system.window(lost).rect, Rect::new(3000, 200, 3800, 800));
+        assert_eq!(state.undo, UndoLog::default());
+    }
+
+    #[test]
+    fn dry_run_touches_no_window() {
+        let mut system = FakeWindowSystem::new(1920, 1080);
+        let lost = system.add(FakeWindow::new("Lost", Rect::new(3000, 200, 3800, 800)));
+        let mut state = State::default();
+        let mut seen = Vec::new();
+
+        let report = run(
+            &mut system,
+            Pass::Rescue,
+            true,
+            &PlanOptions::default(),
+            &mut state,
+            |planned| seen.push(planned.window.id),
+        )
+        .unwrap();
+
+        assert!(system.calls().is_empty());
+        assert!(report.dry_run);
+        assert_eq!(seen, [lost]);
+        assert_eq!(state, State::default());
+        assert_eq!(system.window(lost).rect, Rect::new(3000, 200, 3800, 800));
+    }
+}
@@ -0,0 +1,37 @@
+//! Files kept in `%APPDATA%\moswb`
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// `%APPDATA%\moswb\<name>`
+pub fn app_path(name: &str) -> Option<PathBuf> {
+    let app_data = std::env::var_os("APPDATA")?;
+    Some(PathBuf::from(app_data).join("moswb").join(name))
+}
+
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("Invalid {}", path.display()))
+}
+
+/// Like [`read`], but a missing file is the default value
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    read(path)
+}
+
+/// Write `value` as TOML, creating the directory it goes in
+pub fn save<T: Serialize>(value: &T, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(path, toml::to_string(value)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::{get_display_percent, Rect};
+use crate::plan::{final_actions, initial_actions, PlannedMove, RelocationPlan};
+use crate::rescue::monitor_rects;
+use crate::state;
+use crate::window::Snapshot;
+
+/// Rect a window had before the last run moved it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MovedWindow {
+    pub hwnd: isize,
+    /// A window with the logged handle but another title is not put back
+    pub title: String,
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+/// Windows moved by the last run, kept in `undo.toml` so `moswb restore` can put them back
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UndoLog {
+    pub windows: Vec<MovedWindow>,
+}
+
+impl UndoLog {
+    /// A missing file is an empty log
+    pub fn load(path: &Path) -> Result<Self> {
+        state::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        state::save(self, path)
+    }
+
+    /// `%APPDATA%\moswb\undo.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        state::app_path("undo.toml")
+    }
+
+    /// Forget the previous run and remember the rects the windows moved now had
+    pub fn record(&mut self, moved: &[PlannedMove]) {
+        self.windows = moved
+            .iter()
+            .map(|planned| {
+                let rect = planned.window.restored_rect();
+                MovedWindow {
+                    hwnd: planned.window.id.0,
+                    title: planned.window.title.clone(),
+                    left: rect.left,
+                    top: rect.top,
+                    right: rect.right,
+                    bottom: rect.bottom,
+                }
+            })
+            .collect();
+    }
+
+    /// Move the logged windows still open back to where they were
+    ///
+    /// Windows that were closed, or whose handle now belongs to another window, are skipped.
+    pub fn plan(&self, snapshot: &Snapshot) -> RelocationPlan {
+        let monitors = monitor_rects(&snapshot.monitors);
+        let mut plan = RelocationPlan {
+            failures: snapshot.failures.clone(),
+            ..RelocationPlan::default()
+        };
+        for moved in &self.windows {
+            let Some(window) = snapshot
+                .windows
+                .iter()
+                .find(|window| window.id.0 == moved.hwnd && window.title == moved.title)
+            else {
+                continue;
+            };
+            let mut planned = PlannedMove {
+                window: window.clone(),
+                display_percent: get_display_percent(window.rect, &monitors),
+                old_rect: window.rect,
+                new_rect: Rect::new(moved.left, moved.top, moved.right, moved.bottom),
+                tile: None,
+                actions: initial_actions(window),
+            };
+            final_actions(&mut planned);
+            plan.moves.push(planned);
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::{FakeWindow, FakeWindowSystem};
+    use crate::plan::{apply, PlanOptions};
+    use crate::rescue::rescue_windows;
+
+    #[test]
+    fn puts_windows_back() {
+        let mut system = FakeWindowSystem::new(1920, 1080);
+        let lost = system.add(FakeWindow::new("Lost", Rect::new(3000, 200, 3800, 800)));
+        let closed = system.add(FakeWindow::new("Closed", Rect::new(3000, 200, 3800, 800)));
+        let report = rescue_windows(&mut system, &PlanOptions::default()).unwrap();
+        let mut log = UndoLog::default();
+        log.record(&report.moved);
+        system.window_mut(closed).title = "Reused".to_string();
+
+        let plan = log.plan(&Snapshot::capture(&system).unwrap());
+        apply(&mut system, &plan);
+
+        assert_eq!(plan.moves.len(), 1);
+        assert_eq!(system.window(lost).rect, Rect::new(3000, 200, 3800, 800));
+        assert_eq!(system.window(closed).rect, Rect::new(0, 0, 800, 600));
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let log = UndoLog {
+            windows: vec![MovedWindow {
+                hwnd: 42,
+                title: "Lost".to_string(),
+                left: -900,
+                top: 300,
+                right: -100,
+                bottom: 900,
+            }],
+        };
+        let path = std::env::temp_dir()
+            .join("moswb-undo-test")
+            .join("undo.toml");
+
+        log.save(&path).unwrap();
+        assert_eq!(UndoLog::load(&path).unwrap(), log);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
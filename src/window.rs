@@ -0,0 +1,368 @@
+use std::fmt;
+
+use crate::backend::{Monitor, Styles, WindowId, WindowSystem};
+use crate::error::{Error, WindowContext};
+use crate::geometry::Rect;
+use crate::report::{SkipReason, Skipped};
+
+/// What a top-level window is, as far as rescuing it goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowKind {
+    /// Main windows, the ones shown on the taskbar
+    App,
+    /// Palettes and floating toolbars
+    Tool,
+    /// Dialogs and popups that belong to another window
+    Owned,
+    /// Hidden by DWM, like UWP frames on other virtual desktops
+    Cloaked,
+    /// Click-through or fully transparent overlays
+    Transparent,
+}
+
+impl WindowKind {
+    /// Windows that are not visible to the user win over the taskbar rules
+    pub fn classify(styles: Styles, owned: bool, cloaked: bool) -> Self {
+        if cloaked {
+            WindowKind::Cloaked
+        } else if styles.transparent {
+            WindowKind::Transparent
+        } else if styles.app_window {
+            WindowKind::App
+        } else if styles.tool_window {
+            WindowKind::Tool
+        } else if owned {
+            WindowKind::Owned
+        } else {
+            WindowKind::App
+        }
+    }
+}
+
+impl fmt::Display for WindowKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowKind::App => write!(f, "app window"),
+            WindowKind::Tool => write!(f, "tool window"),
+            WindowKind::Owned => write!(f, "owned window"),
+            WindowKind::Cloaked => write!(f, "cloaked window"),
+            WindowKind::Transparent => write!(f, "transparent window"),
+        }
+    }
+}
+
+/// Kinds of windows rescued besides app windows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Scope {
+    pub tool_windows: bool,
+    pub owned_windows: bool,
+    pub cloaked_windows: bool,
+    pub transparent_windows: bool,
+}
+
+impl Scope {
+    pub fn allows(&self, kind: WindowKind) -> bool {
+        match kind {
+            WindowKind::App => true,
+            WindowKind::Tool => self.tool_windows,
+            WindowKind::Owned => self.owned_windows,
+            WindowKind::Cloaked => self.cloaked_windows,
+            WindowKind::Transparent => self.transparent_windows,
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "app")?;
+        for (allowed, kind) in [
+            (self.tool_windows, "tool"),
+            (self.owned_windows, "owned"),
+            (self.cloaked_windows, "cloaked"),
+            (self.transparent_windows, "transparent"),
+        ] {
+            if allowed {
+                write!(f, ", {kind}")?;
+            }
+        }
+        write!(f, " windows")
+    }
+}
+
+/// Point-in-time snapshot of a top-level window
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowInfo {
+    pub id: WindowId,
+    pub title: String,
+    /// Window class name, empty when it could not be read
+    pub class: String,
+    /// Executable file name of the owning process, empty when it could not be read, which is
+    /// common for elevated processes
+    pub process: String,
+    pub rect: Rect,
+    /// Rect the window returns to when restored
+    pub normal_rect: Rect,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub kind: WindowKind,
+    /// Window this one belongs to, like the main window of a dialog
+    pub owner: Option<WindowId>,
+}
+
+impl WindowInfo {
+    /// Rect the window has once it is neither minimized nor maximized
+    pub fn restored_rect(&self) -> Rect {
+        if self.minimized || self.maximized {
+            self.normal_rect
+        } else {
+            self.rect
+        }
+    }
+
+    /// Query the backend for everything known about a window
+    pub fn capture(system: &impl WindowSystem, id: WindowId) -> Result<Self, Error> {
+        let title = Self::title(system, id)?;
+        Self::capture_titled(system, id, title)
+    }
+
+    fn title(system: &impl WindowSystem, id: WindowId) -> Result<String, Error> {
+        system.window_text(id).map_err(|e| Error::TextDecode {
+            window: WindowContext::new(id, None),
+            message: format!("{e:#}"),
+        })
+    }
+
+    /// Query the rest once the title is known
+    fn capture_titled(
+        system: &impl WindowSystem,
+        id: WindowId,
+        title: String,
+    ) -> Result<Self, Error> {
+        let query_error = |operation, e| {
+            Error::from_backend(
+                WindowContext::new(id, Some(&title)),
+                operation,
+                e,
+                |window, message| Error::RectQuery { window, message },
+            )
+        };
+        let rect = system
+            .window_rect(id)
+            .map_err(|e| query_error("GetWindowRect", e))?;
+        let normal_rect = system
+            .normal_rect(id)
+            .map_err(|e| query_error("GetWindowPlacement", e))?;
+
+        let owner = system.owner(id);
+        Ok(Self {
+            id,
+            class: system.class_name(id).unwrap_or_default(),
+            process: system.process_name(id).unwrap_or_default(),
+            title,
+            rect,
+            normal_rect,
+            minimized: system.is_minimized(id),
+            maximized: system.is_maximized(id),
+            owner,
+            kind: WindowKind::classify(system.styles(id), owner.is_some(), system.is_cloaked(id)),
+        })
+    }
+
+    /// A window known only by its handle, nothing else was queried
+    fn untitled(id: WindowId) -> Self {
+        Self {
+            id,
+            title: String::new(),
+            class: String::new(),
+            process: String::new(),
+            rect: Rect::default(),
+            normal_rect: Rect::default(),
+            minimized: false,
+            maximized: false,
+            kind: WindowKind::App,
+            owner: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl WindowInfo {
+    /// A restored app window for tests to adjust, with struct update syntax or its fields
+    pub(crate) fn for_test(title: &str, rect: Rect) -> Self {
+        Self {
+            id: WindowId(1),
+            title: title.to_string(),
+            class: String::new(),
+            process: String::new(),
+            rect,
+            normal_rect: rect,
+            minimized: false,
+            maximized: false,
+            kind: WindowKind::App,
+            owner: None,
+        }
+    }
+}
+
+/// Every visible top-level window together with the monitors they were captured on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub monitors: Vec<Monitor>,
+    /// Mouse position, if it could be read
+    pub cursor: Option<(i32, i32)>,
+    pub windows: Vec<WindowInfo>,
+    /// Windows left out as soon as their title was read, known only by their handle
+    pub skipped: Vec<Skipped>,
+    /// Windows that could not be captured, enumeration continues past them
+    pub failures: Vec<Error>,
+}
+
+impl Snapshot {
+    /// Position of a window in `windows`
+    pub fn position(&self, id: WindowId) -> Option<usize> {
+        self.windows.iter().position(|window| window.id == id)
+    }
+
+    pub fn capture(system: &impl WindowSystem) -> Result<Self, Error> {
+        let ids = system.windows().map_err(|e| Error::Enumerate {
+            operation: "EnumWindows",
+            message: format!("{e:#}"),
+        })?;
+        let monitors = system.monitors().map_err(|e| Error::Enumerate {
+            operation: "EnumDisplayMonitors",
+            message: format!("{e:#}"),
+        })?;
+
+        let mut windows = Vec::new();
+        let mut skipped = Vec::new();
+        let mut failures = Vec::new();
+        for id in ids {
+            if !system.is_visible(id) {
+                continue;
+            }
+
+            // Untitled windows are never moved, a failed query about one is no failure
+            let captured = WindowInfo::title(system, id).and_then(|title| {
+                if title.is_empty() {
+                    return Ok(None);
+                }
+                WindowInfo::capture_titled(system, id, title).map(Some)
+            });
+            match captured {
+                Ok(Some(window)) => windows.push(window),
+                Ok(None) => skipped.push(Skipped {
+                    window: WindowInfo::untitled(id),
+                    reason: SkipReason::Untitled,
+                }),
+                Err(e) => failures.push(e),
+            }
+        }
+
+        Ok(Self {
+            monitors,
+            cursor: system.cursor_pos().ok(),
+            windows,
+            skipped,
+            failures,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::{FakeWindow, FakeWindowSystem, Fault};
+    use crate::geometry::Rect;
+
+    #[test]
+    fn classifies_windows() {
+        let mut system = FakeWindowSystem::new(1920, 1080);
+        let rect = Rect::new(200, 200, 1000, 800);
+        let main = system.add(FakeWindow::new("Main", rect));
+        let cases = [
+            ("Main", FakeWindow::new("Main", rect), WindowKind::App),
+            (
+                "Tool",
+                FakeWindow::new("Tool", rect).tool_window(),
+                WindowKind::Tool,
+            ),
+            (
+                "Dialog",
+                FakeWindow::new("Dialog", rect).owned_by(main),
+                WindowKind::Owned,
+            ),
+            (
+                "Owned app window",
+                FakeWindow::new("Owned app window", rect)
+                    .owned_by(main)
+                    .app_window(),
+                WindowKind::App,
+            ),
+            (
+                "Tool app window",
+                FakeWindow::new("Tool app window", rect)
+                    .tool_window()
+                    .app_window(),
+                WindowKind::App,
+            ),
+            (
+                "Cloaked",
+                FakeWindow::new("Cloaked", rect).app_window().cloaked(),
+                WindowKind::Cloaked,
+            ),
+            (
+                "Overlay",
+                FakeWindow::new("Overlay", rect).tool_window().transparent(),
+                WindowKind::Transparent,
+            ),
+        ];
+        let ids: Vec<_> = cases
+            .iter()
+            .map(|(_, window, _)| system.add(window.clone()))
+            .collect();
+
+        for ((name, _, expected), id) in cases.iter().zip(ids) {
+            let window = WindowInfo::capture(&system, id).unwrap();
+            assert_eq!(window.kind, *expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn leaves_untitled_windows_out() {
+        let mut system = FakeWindowSystem::new(1920, 1080);
+        let rect = Rect::new(-2000, 200, -1000, 800);
+        system.add(FakeWindow::new("Lost", rect));
+        let untitled = system.add(FakeWindow::new("", rect).failing(Fault::Placement));
+        system.add(FakeWindow::new("Broken", rect).failing(Fault::Placement));
+
+        let snapshot = Snapshot::capture(&system).unwrap();
+
+        let titles: Vec<_> = snapshot.windows.iter().map(|w| w.title.as_str()).collect();
+        assert_eq!(titles, ["Lost"]);
+        assert_eq!(snapshot.skipped.len(), 1);
+        assert_eq!(snapshot.skipped[0].window.id, untitled);
+        assert_eq!(snapshot.skipped[0].reason, SkipReason::Untitled);
+        let codes: Vec<_> = snapshot.failures.iter().map(Error::exit_code).collect();
+        assert_eq!(codes, [5]);
+    }
+
+    #[test]
+    fn default_scope_is_app_windows() {
+        let scope = Scope::default();
+        assert!(scope.allows(WindowKind::App));
+        assert!(!scope.allows(WindowKind::Tool));
+        assert!(!scope.allows(WindowKind::Owned));
+        assert!(!scope.allows(WindowKind::Cloaked));
+        assert!(!scope.allows(WindowKind::Transparent));
+        assert_eq!(scope.to_string(), "app windows");
+
+        let wider = Scope {
+            owned_windows: true,
+            transparent_windows: true,
+            ..Scope::default()
+        };
+        assert!(wider.allows(WindowKind::Owned));
+        assert!(!wider.allows(WindowKind::Tool));
+        assert_eq!(wider.to_string(), "app, owned, transparent windows");
+    }
+}